@@ -0,0 +1,248 @@
+use base64::{Engine as _, engine::general_purpose};
+use flate2::{Compression, write::ZlibEncoder};
+use std::io::{self, Write};
+
+// 17-сегментная модель ЛЖ (AHA) начало
+pub const SEGMENTS: usize = 17;
+
+pub const SEGMENT_NAMES: [&str; SEGMENTS] = [
+    "базальный передний",
+    "базальный переднеперегородочный",
+    "базальный нижнеперегородочный",
+    "базальный нижний",
+    "базальный нижнебоковой",
+    "базальный переднебоковой",
+    "средний передний",
+    "средний переднеперегородочный",
+    "средний нижнеперегородочный",
+    "средний нижний",
+    "средний нижнебоковой",
+    "средний переднебоковой",
+    "верхушечный передний",
+    "верхушечный перегородочный",
+    "верхушечный нижний",
+    "верхушечный боковой",
+    "верхушка",
+];
+
+// заливка и подпись сегмента на диаграмме
+#[derive(Debug, Clone)]
+pub struct SegmentFill {
+    pub color: [u8; 3],
+    pub label: String,
+}
+// 17-сегментная модель ЛЖ (AHA) конец
+
+// геометрия диаграммы начало
+// радиусы колец в пикселях: верхушка, верхушечные, средние, базальные сегменты
+const SIZE: usize = 300;
+const R_APEX: f64 = 32.0;
+const R_APICAL: f64 = 72.0;
+const R_MID: f64 = 110.0;
+const R_BASAL: f64 = 146.0;
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+
+// номер сегмента (с нуля) и признак границы для точки; угол — против часовой от оси x
+fn locate(r: f64, angle: f64) -> Option<(usize, bool)> {
+    let near = |radius: f64| (r - radius).abs() < 1.0;
+    if r > R_BASAL + 1.0 {
+        return None;
+    }
+    if near(R_APEX) || near(R_APICAL) || near(R_MID) || near(R_BASAL) {
+        return Some((0, true));
+    }
+    if r < R_APEX {
+        return Some((16, false));
+    }
+    // перегородка слева, передняя стенка сверху
+    let (first, width, offset) = if r < R_APICAL {
+        (12, 90.0, 45.0)
+    } else if r < R_MID {
+        (6, 60.0, 60.0)
+    } else {
+        (0, 60.0, 60.0)
+    };
+    let shifted = (angle - offset).rem_euclid(360.0);
+    let within = shifted % width;
+    let border = within.min(width - within).to_radians() * r < 1.0;
+    Some((first + (shifted / width) as usize, border))
+}
+
+// центр подписи сегмента
+fn label_center(segment: usize) -> (f64, f64) {
+    let c = SIZE as f64 / 2.0;
+    let (radius, angle) = match segment {
+        16 => return (c, c),
+        12..=15 => (
+            (R_APEX + R_APICAL) / 2.0,
+            90.0 + 90.0 * (segment - 12) as f64,
+        ),
+        6..=11 => ((R_APICAL + R_MID) / 2.0, 90.0 + 60.0 * (segment - 6) as f64),
+        _ => ((R_MID + R_BASAL) / 2.0, 90.0 + 60.0 * segment as f64),
+    };
+    let a = angle.to_radians();
+    (c + radius * a.cos(), c - radius * a.sin())
+}
+// геометрия диаграммы конец
+
+// шрифт 3×5 для подписей начало
+const GLYPH_SCALE: usize = 3;
+
+fn glyph(ch: char) -> Option<[u8; 5]> {
+    let rows = match ch {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' | '−' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+fn draw_label(pixels: &mut [[u8; 3]], label: &str, center: (f64, f64), color: [u8; 3]) {
+    if label.is_empty() {
+        return;
+    }
+    let advance = 4 * GLYPH_SCALE;
+    let width = label.chars().count() * advance - GLYPH_SCALE;
+    // подпись не выходит за левый и правый край изображения
+    let left = (center.0 as usize)
+        .saturating_sub(width / 2)
+        .min(SIZE.saturating_sub(width));
+    let top = (center.1 as usize).saturating_sub(5 * GLYPH_SCALE / 2);
+    for (i, ch) in label.chars().enumerate() {
+        let Some(rows) = glyph(ch) else {
+            continue;
+        };
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        let x = left + i * advance + col * GLYPH_SCALE + dx;
+                        let y = top + row * GLYPH_SCALE + dy;
+                        if x < SIZE && y < SIZE {
+                            pixels[y * SIZE + x] = color;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+// шрифт 3×5 для подписей конец
+
+// PNG начало
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+// RGB 8 бит без фильтрации строк
+fn encode_png(pixels: &[[u8; 3]], width: usize, height: usize) -> io::Result<Vec<u8>> {
+    let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+    for row in pixels.chunks(width) {
+        raw.push(0);
+        raw.extend(row.iter().flatten());
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&raw)?;
+    let idat = encoder.finish()?;
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut out, b"IHDR", &ihdr);
+    png_chunk(&mut out, b"IDAT", &idat);
+    png_chunk(&mut out, b"IEND", &[]);
+    Ok(out)
+}
+// PNG конец
+
+// диаграмма «бычий глаз» в PNG
+pub fn render_png(fills: &[SegmentFill; SEGMENTS]) -> io::Result<Vec<u8>> {
+    let c = SIZE as f64 / 2.0;
+    let mut pixels = vec![WHITE; SIZE * SIZE];
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let dx = x as f64 + 0.5 - c;
+            let dy = c - (y as f64 + 0.5);
+            let angle = dy.atan2(dx).to_degrees();
+            pixels[y * SIZE + x] = match locate(dx.hypot(dy), angle) {
+                None => WHITE,
+                Some((_, true)) => BLACK,
+                Some((segment, false)) => fills[segment].color,
+            };
+        }
+    }
+    for (segment, fill) in fills.iter().enumerate() {
+        let [r, g, b] = fill.color;
+        // на тёмной заливке подпись белая
+        let luminance = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+        let color = if luminance < 128.0 { WHITE } else { BLACK };
+        draw_label(&mut pixels, &fill.label, label_center(segment), color);
+    }
+    encode_png(&pixels, SIZE, SIZE)
+}
+
+// для шаблона: {{img поле ширина высота}} принимает PNG в base64
+pub fn render_base64(fills: &[SegmentFill; SEGMENTS]) -> String {
+    match render_png(fills) {
+        Ok(png) => general_purpose::STANDARD.encode(png),
+        Err(_) => "".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_stays_inside_image() {
+        let mut pixels = vec![WHITE; SIZE * SIZE];
+        // у края и длиннее изображения — без переполнения при вычитании
+        draw_label(&mut pixels, "-12", (0.0, 0.0), BLACK);
+        draw_label(&mut pixels, "-12", (SIZE as f64, SIZE as f64), BLACK);
+        draw_label(&mut pixels, &"8".repeat(40), (150.0, 150.0), BLACK);
+        assert!(pixels.contains(&BLACK));
+
+        // подпись у правого края сдвигается внутрь целиком
+        let mut pixels = vec![WHITE; SIZE * SIZE];
+        draw_label(&mut pixels, "-20", (SIZE as f64 - 1.0, 150.0), BLACK);
+        let width = 3 * 4 * GLYPH_SCALE - GLYPH_SCALE;
+        let leftmost = (0..SIZE)
+            .find(|&x| (0..SIZE).any(|y| pixels[y * SIZE + x] == BLACK))
+            .unwrap();
+        assert!(leftmost >= SIZE - width);
+    }
+
+    #[test]
+    fn png_signature() {
+        let fills = std::array::from_fn(|_| SegmentFill {
+            color: WHITE,
+            label: "-18".to_owned(),
+        });
+        let png = render_png(&fills).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
@@ -0,0 +1,2633 @@
+use crate::bullseye::{SEGMENT_NAMES, SEGMENTS};
+use crate::promptget::{Derived, PreciseNum, RenderToString, checked_div};
+use std::fmt;
+
+// диастолическая функция начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiastolicGrade {
+    Normal,
+    Grade1,
+    Grade2,
+    Grade3,
+    Indeterminate,
+}
+
+impl DiastolicGrade {
+    pub fn text(self) -> &'static str {
+        match self {
+            DiastolicGrade::Normal => "не нарушена",
+            DiastolicGrade::Grade1 => "нарушена по 1 типу",
+            DiastolicGrade::Grade2 => "нарушена по 2 типу",
+            DiastolicGrade::Grade3 => "нарушена по 3 типу",
+            DiastolicGrade::Indeterminate => "не определена",
+        }
+    }
+}
+
+impl fmt::Display for DiastolicGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// входные данные алгоритма ASE/EACVI 2016, скорости E, A, e' в см/с, ТР в м/с
+#[derive(Debug, Clone, Copy)]
+pub struct DiastolicInput {
+    pub peak_e: Option<f64>,
+    pub peak_a: Option<f64>,
+    pub e_sept: Option<f64>,
+    pub e_lat: Option<f64>,
+    pub left_atrium_index: Option<f64>,
+    pub tr_velocity: Option<f64>,
+    pub deceleration_time: Option<f64>,
+    // снижение E/A при пробе Вальсальвы относительно исходного (абсолютная величина)
+    pub valsalva_e_a_change: Option<f64>,
+    pub pv_s_d_ratio: Option<f64>,
+    pub ar_a_duration: Option<f64>,
+    pub ejection_fraction: Option<f64>,
+    pub myocardial_disease: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiastolicFunction {
+    pub grade: DiastolicGrade,
+    pub criteria: Vec<&'static str>,
+}
+
+impl DiastolicFunction {
+    pub fn assess(inp: &DiastolicInput) -> Self {
+        let mut criteria = Vec::new();
+
+        let e_div_e_aps = match (inp.peak_e, inp.e_sept, inp.e_lat) {
+            (Some(e), Some(s), Some(l)) if s + l > 0.0 => Some(e / ((s + l) / 2.0)),
+            _ => None,
+        };
+        let e_aps_low = match (inp.e_sept, inp.e_lat) {
+            (Some(s), Some(l)) => Some(s < 7.0 || l < 10.0),
+            (Some(s), None) => Some(s < 7.0),
+            (None, Some(l)) => Some(l < 10.0),
+            (None, None) => None,
+        };
+        let e_div_e_aps_high = e_div_e_aps.map(|v| v > 14.0);
+        let tr_high = inp.tr_velocity.map(|v| v > 2.8);
+        let lavi_high = inp.left_atrium_index.map(|v| v > 34.0);
+
+        let reduced_ef = inp.ejection_fraction.is_some_and(|ef| ef < 50.0);
+
+        // алгоритм 1: сохранная ФВ без поражения миокарда
+        if !reduced_ef && !inp.myocardial_disease {
+            let checks = [
+                (e_div_e_aps_high, "E/e' ср. > 14"),
+                (e_aps_low, "e' sept < 7 см/с или e' lat < 10 см/с"),
+                (tr_high, "V max ТР > 2,8 м/с"),
+                (lavi_high, "ИОЛП > 34 мл/м²"),
+            ];
+            let (positive, total) = count_criteria(&checks, &mut criteria);
+
+            if total == 0 || positive * 2 == total {
+                return Self {
+                    grade: DiastolicGrade::Indeterminate,
+                    criteria,
+                };
+            }
+            if positive * 2 < total {
+                return Self {
+                    grade: DiastolicGrade::Normal,
+                    criteria,
+                };
+            }
+            criteria.clear();
+        }
+
+        // алгоритм 2: оценка давления наполнения и типа нарушения
+        let (e, a) = match (inp.peak_e, inp.peak_a) {
+            (Some(e), Some(a)) if a > 0.0 => (e, a),
+            _ => {
+                return Self {
+                    grade: DiastolicGrade::Indeterminate,
+                    criteria,
+                };
+            }
+        };
+        let e_div_a = e / a;
+
+        if e_div_a <= 0.8 && e <= 50.0 {
+            criteria.push("E/A ≤ 0,8 и E ≤ 50 см/с");
+            return Self {
+                grade: DiastolicGrade::Grade1,
+                criteria,
+            };
+        }
+        if e_div_a >= 2.0 {
+            criteria.push("E/A ≥ 2");
+            if inp.deceleration_time.is_some_and(|dt| dt < 160.0) {
+                criteria.push("DT < 160 мс");
+            }
+            return Self {
+                grade: DiastolicGrade::Grade3,
+                criteria,
+            };
+        }
+
+        let main = [
+            (e_div_e_aps_high, "E/e' ср. > 14"),
+            (tr_high, "V max ТР > 2,8 м/с"),
+            (lavi_high, "ИОЛП > 34 мл/м²"),
+        ];
+        let (mut positive, mut total) = count_criteria(&main, &mut criteria);
+
+        // дополнительные критерии — только если основных не хватает
+        if total < main.len() {
+            let extra = [
+                (inp.deceleration_time.map(|dt| dt < 160.0), "DT < 160 мс"),
+                (
+                    inp.valsalva_e_a_change
+                        .map(|d| valsalva_positive(e_div_a, d)),
+                    "снижение E/A при пробе Вальсальвы ≥ 50 %",
+                ),
+                (inp.pv_s_d_ratio.map(|r| r < 1.0), "S/D ЛВ < 1"),
+                (inp.ar_a_duration.map(|d| d >= 30.0), "Ar−A ≥ 30 мс"),
+            ];
+            for (value, label) in extra {
+                if total == main.len() {
+                    break;
+                }
+                if let Some(v) = value {
+                    total += 1;
+                    if v {
+                        positive += 1;
+                        criteria.push(label);
+                    }
+                }
+            }
+        }
+
+        let negative = total - positive;
+        let grade = if positive >= 2 {
+            DiastolicGrade::Grade2
+        } else if negative >= 2 {
+            DiastolicGrade::Grade1
+        } else {
+            DiastolicGrade::Indeterminate
+        };
+
+        Self { grade, criteria }
+    }
+}
+
+impl RenderToString for DiastolicFunction {
+    fn render_to_string(&self) -> String {
+        if self.criteria.is_empty() {
+            format!("Диастолическая функция ЛЖ {}. ", self.grade)
+        } else {
+            format!(
+                "Диастолическая функция ЛЖ {} ({}). ",
+                self.grade,
+                self.criteria.join("; ")
+            )
+        }
+    }
+}
+
+// проба Вальсальвы положительна при снижении E/A на ≥ 50 % от исходного
+fn valsalva_positive(e_div_a: f64, change: f64) -> bool {
+    e_div_a > 0.0 && change / e_div_a >= 0.5
+}
+
+fn count_criteria(
+    checks: &[(Option<bool>, &'static str)],
+    criteria: &mut Vec<&'static str>,
+) -> (usize, usize) {
+    let mut positive = 0;
+    let mut total = 0;
+    for (value, label) in checks {
+        if let Some(v) = value {
+            total += 1;
+            if *v {
+                positive += 1;
+                criteria.push(label);
+            }
+        }
+    }
+    (positive, total)
+}
+// диастолическая функция конец
+
+// давление в ПП начало
+// по НПВ (ASE): диаметр в см, коллабирование на вдохе > 50%
+pub fn estimate_right_atrium_pressure(ivc_diameter: f64, collapses_over_50: bool) -> i64 {
+    match (ivc_diameter <= 2.1, collapses_over_50) {
+        (true, true) => 3,
+        (false, false) => 15,
+        _ => 8,
+    }
+}
+// давление в ПП конец
+
+// вероятность легочной гипертензии начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhProbability {
+    Low,
+    Intermediate,
+    High,
+}
+
+impl PhProbability {
+    pub fn text(self) -> &'static str {
+        match self {
+            PhProbability::Low => "низкая",
+            PhProbability::Intermediate => "промежуточная",
+            PhProbability::High => "высокая",
+        }
+    }
+}
+
+impl fmt::Display for PhProbability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// входные данные по ESC/ERS 2022: ТР и ЛР в м/с, размеры в см, TAPSE в см, СДЛА в мм рт.ст.
+#[derive(Debug, Clone, Copy)]
+pub struct PhInput {
+    pub tr_velocity: f64,
+    pub right_ventricle_baz: f64,
+    pub left_ventricle_baz: Option<f64>,
+    pub septum_flattening: bool,
+    pub tapse: Option<f64>,
+    pub systolic_pressure: f64,
+    pub rvot_notching: bool,
+    pub pr_velocity: Option<f64>,
+    pub pulmonary_artery: f64,
+    pub vena: f64,
+    pub ivc_collapses_over_50: bool,
+    pub right_atrium_s: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PulmonaryHypertension {
+    pub probability: PhProbability,
+    pub signs: Vec<&'static str>,
+}
+
+impl PulmonaryHypertension {
+    pub fn assess(inp: &PhInput) -> Self {
+        let mut signs = Vec::new();
+
+        // A: желудочки
+        let ventricles = [
+            (
+                inp.left_ventricle_baz
+                    .filter(|&lv| lv > 0.0)
+                    .map(|lv| inp.right_ventricle_baz / lv > 1.0),
+                "ПЖ/ЛЖ баз > 1,0",
+            ),
+            (Some(inp.septum_flattening), "уплощение МЖП"),
+            (
+                inp.tapse
+                    .filter(|_| inp.systolic_pressure > 0.0)
+                    .map(|t| t * 10.0 / inp.systolic_pressure < 0.55),
+                "TAPSE/СДЛА < 0,55 мм/мм рт.ст.",
+            ),
+        ];
+        // B: легочная артерия
+        let artery = [
+            (Some(inp.rvot_notching), "среднесистолическая выемка в ВТПЖ"),
+            (inp.pr_velocity.map(|v| v > 2.2), "V max ЛР > 2,2 м/с"),
+            (Some(inp.pulmonary_artery > 2.5), "диаметр ЛА > 2,5 см"),
+        ];
+        // C: НПВ и ПП; расширенная НПВ со сниженным коллабированием — ДПП 15 мм рт.ст.
+        let atrium = [
+            (
+                Some(estimate_right_atrium_pressure(inp.vena, inp.ivc_collapses_over_50) >= 15),
+                "НПВ > 2,1 см с коллабированием < 50 %",
+            ),
+            (Some(inp.right_atrium_s > 18.0), "S ПП > 18 см²"),
+        ];
+
+        let categories = [
+            count_criteria(&ventricles, &mut signs).0,
+            count_criteria(&artery, &mut signs).0,
+            count_criteria(&atrium, &mut signs).0,
+        ];
+        let other_signs = categories.iter().filter(|&&n| n > 0).count() >= 2;
+
+        let probability = if inp.tr_velocity > 3.4 {
+            PhProbability::High
+        } else if inp.tr_velocity > 2.8 {
+            if other_signs {
+                PhProbability::High
+            } else {
+                PhProbability::Intermediate
+            }
+        } else if other_signs {
+            PhProbability::Intermediate
+        } else {
+            PhProbability::Low
+        };
+
+        Self { probability, signs }
+    }
+}
+
+impl RenderToString for PulmonaryHypertension {
+    fn render_to_string(&self) -> String {
+        if self.signs.is_empty() {
+            format!(" Вероятность ЛГ {}.", self.probability)
+        } else {
+            format!(
+                " Вероятность ЛГ {} (дополнительные признаки: {}).",
+                self.probability,
+                self.signs.join("; ")
+            )
+        }
+    }
+}
+// вероятность легочной гипертензии конец
+
+// аортальный стеноз начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AsGrade {
+    None,
+    Sclerosis,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl AsGrade {
+    pub fn text(self) -> &'static str {
+        match self {
+            AsGrade::None => "нет",
+            AsGrade::Sclerosis => "аортальный склероз без стеноза",
+            AsGrade::Mild => "незначительный аортальный стеноз",
+            AsGrade::Moderate => "умеренный аортальный стеноз",
+            AsGrade::Severe => "тяжелый аортальный стеноз",
+        }
+    }
+}
+
+impl fmt::Display for AsGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowGradient {
+    Classical,
+    Paradoxical,
+    NormalFlow,
+}
+
+impl LowGradient {
+    pub fn text(self) -> &'static str {
+        match self {
+            LowGradient::Classical => "низкопоточный низкоградиентный стеноз со сниженной ФВ",
+            LowGradient::Paradoxical => {
+                "парадоксальный низкопоточный низкоградиентный стеноз с сохранной ФВ"
+            }
+            LowGradient::NormalFlow => "нормопоточный низкоградиентный стеноз",
+        }
+    }
+}
+
+impl fmt::Display for LowGradient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// скорость в м/с, градиент в мм рт.ст., площадь в см², ИУО в мл/м²
+#[derive(Debug, Clone, Copy)]
+pub struct AsInput {
+    pub max_velocity: f64,
+    pub mid_grad: Option<f64>,
+    pub valve_area: Option<f64>,
+    pub leaflets_changed: bool,
+    pub ejection_fraction: Option<f64>,
+    pub stroke_volume_index: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AorticStenosis {
+    pub grade: AsGrade,
+    pub low_gradient: Option<LowGradient>,
+}
+
+impl AorticStenosis {
+    pub fn assess(inp: &AsInput) -> Self {
+        let by_velocity = match inp.max_velocity {
+            v if v >= 4.0 => AsGrade::Severe,
+            v if v >= 3.0 => AsGrade::Moderate,
+            v if v >= 2.6 => AsGrade::Mild,
+            _ if inp.leaflets_changed => AsGrade::Sclerosis,
+            _ => AsGrade::None,
+        };
+        let by_gradient = match inp.mid_grad {
+            Some(g) if g >= 40.0 => AsGrade::Severe,
+            Some(g) if g >= 20.0 => AsGrade::Moderate,
+            _ => AsGrade::None,
+        };
+        let hemodynamic = by_velocity.max(by_gradient);
+        // площадь > 1,5 см² степень не определяет: наличие стеноза — по скорости
+        let by_area = match inp.valve_area {
+            Some(area) if area < 1.0 => AsGrade::Severe,
+            Some(area) if area <= 1.5 => AsGrade::Moderate,
+            _ => AsGrade::None,
+        };
+        let grade = hemodynamic.max(by_area);
+
+        // малая площадь при невысоких скорости и градиенте, в том числе ниже порога
+        // незначительного стеноза
+        let low_gradient = match inp.valve_area {
+            Some(area) if area < 1.0 && hemodynamic < AsGrade::Severe => {
+                if inp.ejection_fraction.is_some_and(|ef| ef < 50.0) {
+                    Some(LowGradient::Classical)
+                } else if inp.stroke_volume_index.is_some_and(|svi| svi < 35.0) {
+                    Some(LowGradient::Paradoxical)
+                } else {
+                    Some(LowGradient::NormalFlow)
+                }
+            }
+            _ => None,
+        };
+
+        Self {
+            grade,
+            low_gradient,
+        }
+    }
+}
+
+impl RenderToString for AorticStenosis {
+    fn render_to_string(&self) -> String {
+        match (self.grade, self.low_gradient) {
+            (AsGrade::None, _) => "".to_owned(),
+            (grade, None) => format!(" Заключение по АК: {}.", grade),
+            (grade, Some(lg)) => format!(
+                " Заключение по АК: {} по площади отверстия (S < 1,0 см²) при невысоком градиенте — {}, тяжесть требует подтверждения.",
+                grade, lg
+            ),
+        }
+    }
+}
+// аортальный стеноз конец
+
+// регургитации начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegurgitationGrade {
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl RegurgitationGrade {
+    pub fn text(self) -> &'static str {
+        match self {
+            RegurgitationGrade::Mild => "незначительная",
+            RegurgitationGrade::Moderate => "умеренная",
+            RegurgitationGrade::Severe => "выраженная",
+        }
+    }
+}
+
+impl fmt::Display for RegurgitationGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// параметр растёт с тяжестью: < moderate_from — легкая, >= severe_from — тяжелая
+fn grade_ascending(value: f64, moderate_from: f64, severe_from: f64) -> RegurgitationGrade {
+    if value >= severe_from {
+        RegurgitationGrade::Severe
+    } else if value >= moderate_from {
+        RegurgitationGrade::Moderate
+    } else {
+        RegurgitationGrade::Mild
+    }
+}
+
+// количественные параметры (EROA, RVol) приоритетны, иначе — преобладающая степень
+fn integrate_grades(
+    qualitative: &[RegurgitationGrade],
+    quantitative: &[RegurgitationGrade],
+) -> Option<RegurgitationGrade> {
+    if let Some(g) = quantitative.iter().max() {
+        return Some(*g);
+    }
+    prevailing(qualitative)
+}
+
+// самая частая степень, при равенстве — более тяжелая
+fn prevailing<T: Ord + Copy>(grades: &[T]) -> Option<T> {
+    grades
+        .iter()
+        .copied()
+        .max_by_key(|g| (grades.iter().filter(|&q| q == g).count(), *g))
+}
+
+// PHT в мс, VC в см, ширина струи в % от ВТЛЖ, EROA в см², RVol в мл
+#[derive(Debug, Clone, Copy)]
+pub struct ArInput {
+    pub presh_time: Option<f64>,
+    pub vena_contracta: Option<f64>,
+    pub jet_width_ratio: Option<f64>,
+    pub holodiastolic_reversal: Option<bool>,
+    pub eroa: Option<f64>,
+    pub regurgitant_volume: Option<f64>,
+}
+
+pub fn grade_aortic_regurgitation(inp: &ArInput) -> Option<RegurgitationGrade> {
+    let mut qualitative = Vec::new();
+    if let Some(pht) = inp.presh_time {
+        qualitative.push(if pht < 200.0 {
+            RegurgitationGrade::Severe
+        } else if pht <= 500.0 {
+            RegurgitationGrade::Moderate
+        } else {
+            RegurgitationGrade::Mild
+        });
+    }
+    if let Some(vc) = inp.vena_contracta {
+        qualitative.push(if vc > 0.6 {
+            RegurgitationGrade::Severe
+        } else if vc >= 0.3 {
+            RegurgitationGrade::Moderate
+        } else {
+            RegurgitationGrade::Mild
+        });
+    }
+    if let Some(ratio) = inp.jet_width_ratio {
+        qualitative.push(grade_ascending(ratio, 25.0, 65.0));
+    }
+    // отсутствие голодиастолического потока — не довод за легкую степень
+    if inp.holodiastolic_reversal == Some(true) {
+        qualitative.push(RegurgitationGrade::Severe);
+    }
+
+    let mut quantitative = Vec::new();
+    if let Some(eroa) = inp.eroa {
+        quantitative.push(grade_ascending(eroa, 0.10, 0.30));
+    }
+    if let Some(rvol) = inp.regurgitant_volume {
+        quantitative.push(grade_ascending(rvol, 30.0, 60.0));
+    }
+
+    integrate_grades(&qualitative, &quantitative)
+}
+
+// VC в см, EROA в см², RVol в мл
+#[derive(Debug, Clone, Copy)]
+pub struct MrInput {
+    pub vena_contracta: Option<f64>,
+    pub eroa: Option<f64>,
+    pub regurgitant_volume: Option<f64>,
+}
+
+pub fn grade_mitral_regurgitation(inp: &MrInput) -> Option<RegurgitationGrade> {
+    let qualitative: Vec<RegurgitationGrade> = inp
+        .vena_contracta
+        .map(|vc| grade_ascending(vc, 0.3, 0.7))
+        .into_iter()
+        .collect();
+
+    let mut quantitative = Vec::new();
+    if let Some(eroa) = inp.eroa {
+        quantitative.push(grade_ascending(eroa, 0.20, 0.40));
+    }
+    if let Some(rvol) = inp.regurgitant_volume {
+        quantitative.push(grade_ascending(rvol, 30.0, 60.0));
+    }
+
+    integrate_grades(&qualitative, &quantitative)
+}
+// регургитации конец
+
+// стенозы МК и ЛА начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StenosisGrade {
+    None,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl StenosisGrade {
+    // в родительном падеже для «стеноза … степени»
+    pub fn text(self) -> &'static str {
+        match self {
+            StenosisGrade::None => "нет",
+            StenosisGrade::Mild => "незначительной",
+            StenosisGrade::Moderate => "умеренной",
+            StenosisGrade::Severe => "выраженной",
+        }
+    }
+}
+
+impl fmt::Display for StenosisGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// градиент и СДЛА в мм рт.ст., площадь в см²
+#[derive(Debug, Clone, Copy)]
+pub struct MsInput {
+    pub mid_grad: Option<f64>,
+    pub valve_area: Option<f64>,
+    pub systolic_pressure: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MitralStenosis {
+    pub grade: StenosisGrade,
+    pub criteria: Vec<String>,
+}
+
+impl MitralStenosis {
+    pub fn assess(inp: &MsInput) -> Self {
+        let mut criteria = Vec::new();
+
+        // площадь — основной критерий, без неё — средний градиент
+        let grade = match (inp.valve_area, inp.mid_grad) {
+            (Some(area), _) if area > 2.0 => StenosisGrade::None,
+            (Some(area), _) => {
+                criteria.push(format!("S МК {} см²", PreciseNum::from_float(area, 1)));
+                if area < 1.0 {
+                    StenosisGrade::Severe
+                } else if area <= 1.5 {
+                    StenosisGrade::Moderate
+                } else {
+                    StenosisGrade::Mild
+                }
+            }
+            (None, Some(g)) if g > 10.0 => StenosisGrade::Severe,
+            (None, Some(g)) if g >= 5.0 => StenosisGrade::Moderate,
+            // Gr ср < 5 мм рт.ст. без площади не отличить от нормального клапана
+            (None, _) => StenosisGrade::None,
+        };
+        // СДЛА — вспомогательный критерий (< 30, 30-50, > 50 мм рт.ст.): без площади
+        // повышает умеренный стеноз по градиенту до выраженного
+        let grade = if inp.valve_area.is_none()
+            && grade == StenosisGrade::Moderate
+            && inp.systolic_pressure > 50.0
+        {
+            StenosisGrade::Severe
+        } else {
+            grade
+        };
+
+        if grade != StenosisGrade::None {
+            if let Some(g) = inp.mid_grad {
+                criteria.push(format!("Gr ср {} мм рт.ст.", PreciseNum::from_float(g, 1)));
+            }
+            if inp.systolic_pressure >= 30.0 {
+                criteria.push(format!(
+                    "СДЛА {} мм рт.ст.",
+                    PreciseNum::from_float(inp.systolic_pressure, 0)
+                ));
+            }
+        }
+
+        Self { grade, criteria }
+    }
+}
+
+impl RenderToString for MitralStenosis {
+    fn render_to_string(&self) -> String {
+        match self.grade {
+            StenosisGrade::None => "".to_owned(),
+            grade => format!(
+                " Признаки митрального стеноза {} степени ({}).",
+                grade,
+                self.criteria.join(", ")
+            ),
+        }
+    }
+}
+// стеноз ЛА по макс. градиенту, мм рт.ст.
+#[derive(Debug, Clone, Copy)]
+pub struct PulmonaryStenosis {
+    pub grade: StenosisGrade,
+    pub max_grad: f64,
+}
+
+impl PulmonaryStenosis {
+    pub fn assess(max_grad: f64) -> Self {
+        let grade = if max_grad > 64.0 {
+            StenosisGrade::Severe
+        } else if max_grad >= 36.0 {
+            StenosisGrade::Moderate
+        } else if max_grad >= 16.0 {
+            StenosisGrade::Mild
+        } else {
+            StenosisGrade::None
+        };
+        Self { grade, max_grad }
+    }
+}
+
+impl RenderToString for PulmonaryStenosis {
+    fn render_to_string(&self) -> String {
+        match self.grade {
+            StenosisGrade::None => "".to_owned(),
+            grade => format!(
+                " Признаки стеноза ЛА {} степени (Gr max {} мм рт.ст.).",
+                grade,
+                PreciseNum::from_float(self.max_grad, 0)
+            ),
+        }
+    }
+}
+// стенозы МК и ЛА конец
+
+// протезы клапанов начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProsthesisGrade {
+    Normal,
+    PossibleStenosis,
+    SignificantStenosis,
+    Regurgitation,
+}
+
+impl ProsthesisGrade {
+    pub fn text(self) -> &'static str {
+        match self {
+            ProsthesisGrade::Normal => "данных за дисфункцию протеза не получено",
+            ProsthesisGrade::PossibleStenosis => "возможен стеноз протеза",
+            ProsthesisGrade::SignificantStenosis => "признаки значимого стеноза протеза",
+            ProsthesisGrade::Regurgitation => "признаки регургитантной дисфункции протеза",
+        }
+    }
+}
+
+impl fmt::Display for ProsthesisGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PatientProsthesisMismatch {
+    None,
+    Moderate,
+    Severe,
+}
+
+impl PatientProsthesisMismatch {
+    pub fn text(self) -> &'static str {
+        match self {
+            PatientProsthesisMismatch::None => "нет",
+            PatientProsthesisMismatch::Moderate => "умеренное несоответствие протеза пациенту",
+            PatientProsthesisMismatch::Severe => "выраженное несоответствие протеза пациенту",
+        }
+    }
+}
+
+impl fmt::Display for PatientProsthesisMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// усредненные нормальные EOA протезов АК по типу и размеру, см² (Pibarot, Dumesnil 2009):
+// двустворчатые механические и каркасные биологические, 19-29 мм
+const REFERENCE_EOA_SIZES: [f64; 6] = [19.0, 21.0, 23.0, 25.0, 27.0, 29.0];
+const REFERENCE_EOA_MECHANICAL: [f64; 6] = [1.0, 1.3, 1.6, 1.9, 2.3, 2.8];
+const REFERENCE_EOA_BIOLOGICAL: [f64; 6] = [1.1, 1.3, 1.5, 1.7, 1.9, 2.1];
+// допустимое отклонение EOA от эталонной (~1 SD), см²
+const REFERENCE_EOA_TOLERANCE: f64 = 0.3;
+
+// эталонная EOA для типа и размера протеза; размеры вне таблицы — None
+pub fn reference_eoa(mechanical: bool, size: f64) -> Option<f64> {
+    let table = if mechanical {
+        &REFERENCE_EOA_MECHANICAL
+    } else {
+        &REFERENCE_EOA_BIOLOGICAL
+    };
+    REFERENCE_EOA_SIZES
+        .iter()
+        .position(|&s| s == size.round())
+        .map(|i| table[i])
+}
+
+// нормы ASE 2009 для протеза АК: скорость в м/с, градиент в мм рт.ст., EOA в см², AcT в мс;
+// EOA сравнивается с эталонной для типа и размера протеза, если она известна
+#[derive(Debug, Clone, Copy)]
+pub struct AorticProsthesisInput {
+    pub mechanical: bool,
+    pub size: f64,
+    pub max_velocity: f64,
+    pub mid_grad: Option<f64>,
+    pub dvi: Option<f64>,
+    pub eoa: Option<f64>,
+    pub eoa_index: Option<f64>,
+    pub acceleration_time: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AorticProsthesisFunction {
+    pub grade: ProsthesisGrade,
+    pub reference_eoa: Option<f64>,
+    pub mismatch: Option<PatientProsthesisMismatch>,
+}
+
+impl AorticProsthesisFunction {
+    pub fn assess(inp: &AorticProsthesisInput) -> Self {
+        use ProsthesisGrade::{Normal, PossibleStenosis, SignificantStenosis};
+
+        let mut grades = vec![match inp.max_velocity {
+            v if v > 4.0 => SignificantStenosis,
+            v if v >= 3.0 => PossibleStenosis,
+            _ => Normal,
+        }];
+        if let Some(g) = inp.mid_grad {
+            grades.push(if g > 35.0 {
+                SignificantStenosis
+            } else if g >= 20.0 {
+                PossibleStenosis
+            } else {
+                Normal
+            });
+        }
+        if let Some(dvi) = inp.dvi {
+            grades.push(if dvi < 0.25 {
+                SignificantStenosis
+            } else if dvi < 0.30 {
+                PossibleStenosis
+            } else {
+                Normal
+            });
+        }
+        let reference_eoa = reference_eoa(inp.mechanical, inp.size);
+        if let Some(eoa) = inp.eoa {
+            grades.push(match reference_eoa {
+                _ if eoa < 0.8 => SignificantStenosis,
+                // протез малого размера может иметь EOA ≤ 1,2 см² без стеноза
+                Some(r) if eoa < r - REFERENCE_EOA_TOLERANCE => PossibleStenosis,
+                Some(_) => Normal,
+                None if eoa <= 1.2 => PossibleStenosis,
+                None => Normal,
+            });
+        }
+        if let Some(at) = inp.acceleration_time {
+            grades.push(if at > 100.0 {
+                SignificantStenosis
+            } else if at >= 80.0 {
+                PossibleStenosis
+            } else {
+                Normal
+            });
+        }
+
+        let mismatch = inp.eoa_index.map(|i| {
+            if i <= 0.65 {
+                PatientProsthesisMismatch::Severe
+            } else if i <= 0.85 {
+                PatientProsthesisMismatch::Moderate
+            } else {
+                PatientProsthesisMismatch::None
+            }
+        });
+
+        Self {
+            grade: prevailing(&grades).unwrap_or(Normal),
+            reference_eoa,
+            mismatch,
+        }
+    }
+}
+
+// протезы МК и ТК: E в м/с, градиент в мм рт.ст., PHT в мс
+#[derive(Debug, Clone, Copy)]
+pub struct AvProsthesisInput {
+    pub peak_e: f64,
+    pub mid_grad: f64,
+    pub presh_time: Option<f64>,
+    pub vti_ratio: Option<f64>,
+    // умеренная или выраженная парапротезная фистула
+    pub significant_leak: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AvProsthesisFunction {
+    pub grade: ProsthesisGrade,
+    // высокие скорости при нормальном PHT — вероятна регургитация, а не стеноз
+    pub regurgitation_suspected: bool,
+}
+
+impl AvProsthesisFunction {
+    // значимая фистула — дисфункция протеза, даже если стеноза нет
+    fn with_leak(grade: ProsthesisGrade, inp: &AvProsthesisInput) -> ProsthesisGrade {
+        if grade == ProsthesisGrade::Normal && inp.significant_leak {
+            ProsthesisGrade::Regurgitation
+        } else {
+            grade
+        }
+    }
+
+    // нормы ASE 2009 для протеза МК
+    pub fn assess_mitral(inp: &AvProsthesisInput) -> Self {
+        use ProsthesisGrade::{Normal, PossibleStenosis, SignificantStenosis};
+
+        let mut grades = vec![
+            match inp.peak_e {
+                e if e >= 2.5 => SignificantStenosis,
+                e if e >= 1.9 => PossibleStenosis,
+                _ => Normal,
+            },
+            match inp.mid_grad {
+                g if g > 10.0 => SignificantStenosis,
+                g if g > 5.0 => PossibleStenosis,
+                _ => Normal,
+            },
+        ];
+        if let Some(ratio) = inp.vti_ratio {
+            grades.push(if ratio > 2.5 {
+                SignificantStenosis
+            } else if ratio >= 2.2 {
+                PossibleStenosis
+            } else {
+                Normal
+            });
+        }
+        if let Some(pht) = inp.presh_time {
+            grades.push(if pht > 200.0 {
+                SignificantStenosis
+            } else if pht >= 130.0 {
+                PossibleStenosis
+            } else {
+                Normal
+            });
+        }
+
+        let regurgitation_suspected = inp.presh_time.is_some_and(|pht| pht < 130.0)
+            && (inp.peak_e >= 1.9 || inp.vti_ratio.is_some_and(|r| r > 2.2));
+
+        Self {
+            grade: if regurgitation_suspected {
+                ProsthesisGrade::Regurgitation
+            } else {
+                Self::with_leak(prevailing(&grades).unwrap_or(Normal), inp)
+            },
+            regurgitation_suspected,
+        }
+    }
+
+    // ASE 2009 для протеза ТК: E > 1,7 м/с, Gr ср ≥ 6 мм рт.ст., PHT ≥ 230 мс;
+    // один признак — возможен стеноз, два и более — значимый
+    pub fn assess_tricuspid(inp: &AvProsthesisInput) -> Self {
+        let signs = [
+            inp.peak_e > 1.7,
+            inp.mid_grad >= 6.0,
+            inp.presh_time.is_some_and(|pht| pht >= 230.0),
+        ]
+        .into_iter()
+        .filter(|&b| b)
+        .count();
+
+        Self {
+            grade: Self::with_leak(
+                match signs {
+                    0 => ProsthesisGrade::Normal,
+                    1 => ProsthesisGrade::PossibleStenosis,
+                    _ => ProsthesisGrade::SignificantStenosis,
+                },
+                inp,
+            ),
+            regurgitation_suspected: false,
+        }
+    }
+}
+// протезы клапанов конец
+
+// размеры аорты начало
+// синусы Вальсальвы — Devereux 2012: пол 1 — мужской, 2 — женский; SEE 0,261 см
+const SINUS_SEE: f64 = 0.261;
+
+fn expected_sinus_diameter(age: f64, body_surface_area: f64, male: bool) -> f64 {
+    let sex = if male { 1.0 } else { 2.0 };
+    2.423 + 0.009 * age + 0.461 * body_surface_area - 0.267 * sex
+}
+
+// восходящий отдел — Campens 2014, взрослые: a + b · возраст + c · ППТ, отдельно по полу;
+// (a, b, c, SEE в см)
+const ASCENDING_MALE: (f64, f64, f64, f64) = (1.35, 0.011, 0.58, 0.30);
+const ASCENDING_FEMALE: (f64, f64, f64, f64) = (1.30, 0.011, 0.50, 0.28);
+
+// должный диаметр и SEE восходящего отдела
+fn expected_ascending_diameter(age: f64, body_surface_area: f64, male: bool) -> (f64, f64) {
+    let (a, b, c, see) = if male {
+        ASCENDING_MALE
+    } else {
+        ASCENDING_FEMALE
+    };
+    (a + b * age + c * body_surface_area, see)
+}
+
+// диаметры в см, ППТ в м², рост в м
+#[derive(Debug, Clone, Copy)]
+pub struct AortaInput {
+    pub sinus_diameter: f64,
+    pub ascending_diameter: f64,
+    pub age: f64,
+    pub body_surface_area: f64,
+    pub height: f64,
+    pub male: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AorticSize {
+    pub sinus_expected: f64,
+    pub sinus_z: f64,
+    pub sinus_index: f64,
+    pub sinus_height_index: f64,
+    pub ascending_expected: f64,
+    pub ascending_z: f64,
+    pub ascending_index: f64,
+    pub ascending_height_index: f64,
+}
+
+impl AorticSize {
+    pub fn assess(inp: &AortaInput) -> Self {
+        let sinus_expected = expected_sinus_diameter(inp.age, inp.body_surface_area, inp.male);
+        let (ascending_expected, ascending_see) =
+            expected_ascending_diameter(inp.age, inp.body_surface_area, inp.male);
+
+        Self {
+            sinus_expected,
+            sinus_z: (inp.sinus_diameter - sinus_expected) / SINUS_SEE,
+            sinus_index: inp.sinus_diameter / inp.body_surface_area,
+            sinus_height_index: inp.sinus_diameter / inp.height,
+            ascending_expected,
+            ascending_z: (inp.ascending_diameter - ascending_expected) / ascending_see,
+            ascending_index: inp.ascending_diameter / inp.body_surface_area,
+            ascending_height_index: inp.ascending_diameter / inp.height,
+        }
+    }
+
+    pub fn sinus_dilated(&self) -> bool {
+        self.sinus_z > 2.0
+    }
+
+    pub fn ascending_dilated(&self) -> bool {
+        self.ascending_z > 2.0
+    }
+}
+
+impl RenderToString for AorticSize {
+    fn render_to_string(&self) -> String {
+        match (self.sinus_dilated(), self.ascending_dilated()) {
+            (true, true) => {
+                " Расширение аорты на уровне синусов Вальсальвы и восходящего отдела (z > 2)."
+                    .to_owned()
+            }
+            (true, false) => " Расширение аорты на уровне синусов Вальсальвы (z > 2).".to_owned(),
+            (false, true) => " Расширение восходящего отдела аорты (z > 2).".to_owned(),
+            (false, false) => "".to_owned(),
+        }
+    }
+}
+// размеры аорты конец
+
+// функция ПЖ начало
+// площади в см², S' в см/с, TAPSE в см, СДЛА в мм рт.ст.
+#[derive(Debug, Clone, Copy)]
+pub struct RvInput {
+    pub end_diastolic_area: Option<f64>,
+    pub end_systolic_area: Option<f64>,
+    pub s_prime: Option<f64>,
+    pub rimp: Option<f64>,
+    pub tapse: Option<f64>,
+    pub systolic_pressure: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RvFunction {
+    pub fac: Option<f64>,
+    pub s_prime: Option<f64>,
+    pub rimp: Option<f64>,
+    // TAPSE/СДЛА, мм/мм рт.ст.
+    pub tapse_spap: Option<f64>,
+}
+
+impl RvFunction {
+    pub fn assess(inp: &RvInput) -> Self {
+        let fac = match (inp.end_diastolic_area, inp.end_systolic_area) {
+            (Some(eda), Some(esa)) if eda > 0.0 => Some((eda - esa) / eda * 100.0),
+            _ => None,
+        };
+        let tapse_spap = inp
+            .tapse
+            .filter(|_| inp.systolic_pressure > 0.0)
+            .map(|t| t * 10.0 / inp.systolic_pressure);
+        Self {
+            fac,
+            s_prime: inp.s_prime,
+            rimp: inp.rimp,
+            tapse_spap,
+        }
+    }
+
+    // ФИП ПЖ < 35 %, S' < 9,5 см/с, RIMP (TDI) > 0,54
+    pub fn is_impaired(&self) -> bool {
+        self.fac.is_some_and(|v| v < 35.0)
+            || self.s_prime.is_some_and(|v| v < 9.5)
+            || self.rimp.is_some_and(|v| v > 0.54)
+    }
+
+    // разобщение ПЖ и ЛА: TAPSE/СДЛА < 0,55 мм/мм рт.ст.
+    pub fn is_uncoupled(&self) -> bool {
+        self.tapse_spap.is_some_and(|v| v < 0.55)
+    }
+}
+
+impl RenderToString for RvFunction {
+    fn render_to_string(&self) -> String {
+        let flag = |abnormal: bool| if abnormal { " — снижена" } else { "" };
+        let mut params: Vec<String> = Vec::new();
+        if let Some(fac) = self.fac {
+            params.push(format!(
+                "ФИП ПЖ {} % (N≥35 %){}",
+                PreciseNum::from_float(fac, 0),
+                flag(fac < 35.0)
+            ));
+        }
+        if let Some(s) = self.s_prime {
+            params.push(format!(
+                "S' ТК {} см/с (N≥9,5 см/с){}",
+                PreciseNum::from_float(s, 1),
+                flag(s < 9.5)
+            ));
+        }
+        if let Some(rimp) = self.rimp {
+            params.push(format!(
+                "RIMP {} (N≤0,54){}",
+                PreciseNum::from_float(rimp, 2),
+                if rimp > 0.54 {
+                    " — повышен"
+                } else {
+                    ""
+                }
+            ));
+        }
+        if let Some(ratio) = self.tapse_spap {
+            params.push(format!(
+                "TAPSE/СДЛА {} мм/мм рт.ст. (N≥0,55){}",
+                PreciseNum::from_float(ratio, 2),
+                if self.is_uncoupled() {
+                    " — разобщение ПЖ и ЛА"
+                } else {
+                    ""
+                }
+            ));
+        }
+        if params.is_empty() {
+            return "".to_owned();
+        }
+        let mut out = params.join(", ");
+        if self.is_impaired() {
+            out.push_str(". Систолическая функция ПЖ снижена");
+        }
+        out
+    }
+}
+// функция ПЖ конец
+
+// деформация (speckle tracking) начало
+// strain в %; систолическая деформация ЛЖ и ПЖ отрицательная, сравнивается по модулю;
+// GLS и ПЖ, введенные без минуса, считаются отрицательными, а положительная региональная
+// деформация — систолическое удлинение сегмента (дискинез)
+const GLS_NORMAL: f64 = 18.0;
+const GLS_REDUCED: f64 = 16.0;
+const RV_FREE_WALL_NORMAL: f64 = 20.0;
+const LA_RESERVOIR_NORMAL: f64 = 18.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrainGrade {
+    Normal,
+    Borderline,
+    Reduced,
+}
+
+impl StrainGrade {
+    pub fn text(self) -> &'static str {
+        match self {
+            StrainGrade::Normal => "в норме",
+            StrainGrade::Borderline => "пограничное значение",
+            StrainGrade::Reduced => "снижена",
+        }
+    }
+
+    // GLS и региональная деформация ЛЖ: норма ≤ −18 %, снижена > −16 %
+    pub fn of_left_ventricle(strain: f64) -> Self {
+        let magnitude = -strain;
+        if magnitude >= GLS_NORMAL {
+            StrainGrade::Normal
+        } else if magnitude >= GLS_REDUCED {
+            StrainGrade::Borderline
+        } else {
+            StrainGrade::Reduced
+        }
+    }
+}
+
+impl fmt::Display for StrainGrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StrainInput {
+    pub gls: f64,
+    pub segments: Option<[Option<f64>; SEGMENTS]>,
+    pub rv_free_wall: Option<f64>,
+    pub la_reservoir: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrainFunction {
+    pub gls: StrainGrade,
+    // номера сегментов (с 1) со сниженной региональной деформацией
+    pub reduced_segments: Vec<usize>,
+    // свободная стенка ПЖ > −20 %
+    pub rv_reduced: Option<bool>,
+    // резервуарная деформация ЛП < 18 %
+    pub la_reduced: Option<bool>,
+}
+
+impl StrainFunction {
+    pub fn assess(inp: &StrainInput) -> Self {
+        let reduced_segments = inp
+            .segments
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, v)| {
+                v.is_some_and(|v| StrainGrade::of_left_ventricle(v) == StrainGrade::Reduced)
+            })
+            .map(|(i, _)| i + 1)
+            .collect();
+        Self {
+            gls: StrainGrade::of_left_ventricle(-inp.gls.abs()),
+            reduced_segments,
+            rv_reduced: inp.rv_free_wall.map(|v| v.abs() < RV_FREE_WALL_NORMAL),
+            la_reduced: inp.la_reservoir.map(|v| v < LA_RESERVOIR_NORMAL),
+        }
+    }
+}
+// деформация (speckle tracking) конец
+
+// локальная сократимость ЛЖ начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WallMotion {
+    Normal,
+    Hypokinesis,
+    Akinesis,
+    Dyskinesis,
+    Aneurysm,
+}
+
+impl WallMotion {
+    pub fn from_score(score: u8) -> Option<Self> {
+        match score {
+            1 => Some(WallMotion::Normal),
+            2 => Some(WallMotion::Hypokinesis),
+            3 => Some(WallMotion::Akinesis),
+            4 => Some(WallMotion::Dyskinesis),
+            5 => Some(WallMotion::Aneurysm),
+            _ => None,
+        }
+    }
+
+    pub fn score(self) -> u8 {
+        match self {
+            WallMotion::Normal => 1,
+            WallMotion::Hypokinesis => 2,
+            WallMotion::Akinesis => 3,
+            WallMotion::Dyskinesis => 4,
+            WallMotion::Aneurysm => 5,
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            WallMotion::Normal => "нормокинез",
+            WallMotion::Hypokinesis => "гипокинез",
+            WallMotion::Akinesis => "акинез",
+            WallMotion::Dyskinesis => "дискинез",
+            WallMotion::Aneurysm => "аневризма",
+        }
+    }
+
+    pub fn is_normal(self) -> bool {
+        matches!(self, WallMotion::Normal)
+    }
+}
+
+impl fmt::Display for WallMotion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoronaryTerritory {
+    Lad,
+    Rca,
+    Lcx,
+}
+
+impl CoronaryTerritory {
+    pub fn text(self) -> &'static str {
+        match self {
+            CoronaryTerritory::Lad => "ПНА",
+            CoronaryTerritory::Rca => "ПКА",
+            CoronaryTerritory::Lcx => "ОА",
+        }
+    }
+
+    // типичное кровоснабжение сегментов (номер с 1, AHA 2002); вне 1-17 — None
+    pub fn of_segment(segment: usize) -> Option<Self> {
+        match segment {
+            1 | 2 | 7 | 8 | 13 | 14 | 17 => Some(CoronaryTerritory::Lad),
+            3 | 4 | 9 | 10 | 15 => Some(CoronaryTerritory::Rca),
+            5 | 6 | 11 | 12 | 16 => Some(CoronaryTerritory::Lcx),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CoronaryTerritory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+pub type WallMotionScores = [Option<WallMotion>; SEGMENTS];
+
+// сегменты (номер с 1) по бассейнам в порядке ПНА, ПКА, ОА; пустые бассейны опускаются
+pub type ByTerritory = Vec<(CoronaryTerritory, Vec<(usize, WallMotion)>)>;
+
+fn by_territory(segments: &[(usize, WallMotion)]) -> ByTerritory {
+    [
+        CoronaryTerritory::Lad,
+        CoronaryTerritory::Rca,
+        CoronaryTerritory::Lcx,
+    ]
+    .into_iter()
+    .map(|territory| {
+        let in_territory: Vec<(usize, WallMotion)> = segments
+            .iter()
+            .copied()
+            .filter(|&(n, _)| CoronaryTerritory::of_segment(n) == Some(territory))
+            .collect();
+        (territory, in_territory)
+    })
+    .filter(|(_, s)| !s.is_empty())
+    .collect()
+}
+
+// «гипокинез 7 (средний передний), 13 (верхушечный передний); акинез 4 (базальный нижний)»
+fn describe_segments(segments: &[(usize, WallMotion)]) -> String {
+    let mut groups: Vec<String> = Vec::new();
+    for motion in [
+        WallMotion::Hypokinesis,
+        WallMotion::Akinesis,
+        WallMotion::Dyskinesis,
+        WallMotion::Aneurysm,
+    ] {
+        let named: Vec<String> = segments
+            .iter()
+            .filter(|&&(_, m)| m == motion)
+            .map(|&(n, _)| format!("{} ({})", n, SEGMENT_NAMES[n - 1]))
+            .collect();
+        if !named.is_empty() {
+            groups.push(format!("{} {}", motion, named.join(", ")));
+        }
+    }
+    groups.join("; ")
+}
+
+#[derive(Debug, Clone)]
+pub struct WallMotionAnalysis {
+    // ИНЛС: сумма баллов / число визуализированных сегментов, норма 1,0
+    pub wmsi: Derived,
+    // нарушения по бассейнам: номера сегментов (с 1) и тип нарушения
+    pub affected: ByTerritory,
+    pub not_visualized: usize,
+}
+
+impl WallMotionAnalysis {
+    pub fn assess(scores: &WallMotionScores) -> Self {
+        let visualized: Vec<WallMotion> = scores.iter().flatten().copied().collect();
+        let total: u32 = visualized.iter().map(|m| m.score() as u32).sum();
+        let wmsi = checked_div(
+            total as f64,
+            visualized.len() as f64,
+            "число визуализированных сегментов",
+        );
+        let abnormal: Vec<(usize, WallMotion)> = scores
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| m.map(|m| (i + 1, m)))
+            .filter(|&(_, m)| !m.is_normal())
+            .collect();
+        Self {
+            wmsi,
+            affected: by_territory(&abnormal),
+            not_visualized: SEGMENTS - visualized.len(),
+        }
+    }
+
+    pub fn has_abnormalities(&self) -> bool {
+        !self.affected.is_empty()
+    }
+}
+
+impl RenderToString for WallMotionAnalysis {
+    fn render_to_string(&self) -> String {
+        let wmsi = match self.wmsi {
+            Ok(v) => PreciseNum::from_float(v, 2).to_string(),
+            Err(_) => "не рассчитан".to_owned(),
+        };
+        let mut out = "Локальная сократимость ЛЖ (17 сегментов): ".to_owned();
+        if self.has_abnormalities() {
+            out.push_str(&format!("ИНЛС {} (N 1,0).", wmsi));
+        } else {
+            out.push_str(&format!(
+                "зон нарушения локальной сократимости не выявлено, ИНЛС {}.",
+                wmsi
+            ));
+        }
+        for (territory, segments) in &self.affected {
+            out.push_str(&format!(
+                " Бассейн {}: {}.",
+                territory,
+                describe_segments(segments)
+            ));
+        }
+        if self.not_visualized > 0 {
+            out.push_str(&format!(
+                " Не визуализировано сегментов: {}.",
+                self.not_visualized
+            ));
+        }
+        out.push(' ');
+        out
+    }
+}
+// локальная сократимость ЛЖ конец
+
+// стресс-ЭхоКГ начало
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressResult {
+    Positive,
+    Negative,
+    NonDiagnostic,
+}
+
+impl StressResult {
+    pub fn text(self) -> &'static str {
+        match self {
+            StressResult::Positive => "Проба положительная: индуцируемая ишемия миокарда",
+            StressResult::Negative => {
+                "Проба отрицательная: данных за индуцируемую ишемию миокарда не получено"
+            }
+            StressResult::NonDiagnostic => {
+                "Проба неинформативна: целевая ЧСС не достигнута, новых нарушений локальной сократимости не выявлено"
+            }
+        }
+    }
+}
+
+impl fmt::Display for StressResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StressInput<'a> {
+    pub rest: &'a WallMotionScores,
+    // низкая доза — только при добутаминовой пробе
+    pub low_dose: Option<&'a WallMotionScores>,
+    pub peak: &'a WallMotionScores,
+    pub recovery: &'a WallMotionScores,
+    pub target_reached: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StressEcho {
+    pub result: StressResult,
+    // новые или усугубившиеся нарушения на нагрузке и в восстановлении (худший балл);
+    // при двухфазном ответе — балл на пике
+    pub ischemic: ByTerritory,
+    // сегменты, нарушенные в покое и стойко улучшившиеся на низкой дозе и пике
+    pub viable: Vec<usize>,
+    // двухфазный ответ: улучшение на низкой дозе и ухудшение на пике
+    pub biphasic: Vec<usize>,
+    // нарушения, сохраняющиеся в восстановительном периоде
+    pub persistent: Vec<usize>,
+}
+
+impl StressEcho {
+    pub fn assess(inp: &StressInput) -> Self {
+        let mut worsened: Vec<(usize, WallMotion)> = Vec::new();
+        let mut viable: Vec<usize> = Vec::new();
+        let mut biphasic: Vec<usize> = Vec::new();
+        let mut persistent: Vec<usize> = Vec::new();
+        for i in 0..SEGMENTS {
+            let Some(rest) = inp.rest[i] else {
+                continue;
+            };
+            let low = inp.low_dose.and_then(|s| s[i]);
+            let peak = inp.peak[i];
+            let recovery = inp.recovery[i];
+            if recovery.is_some_and(|r| r > rest) {
+                persistent.push(i + 1);
+            }
+
+            let improved_at_low = !rest.is_normal() && low.is_some_and(|low| low < rest);
+            if improved_at_low
+                && let (Some(low), Some(peak)) = (low, peak)
+                && peak > low
+            {
+                biphasic.push(i + 1);
+                worsened.push((i + 1, peak));
+                continue;
+            }
+            if improved_at_low {
+                viable.push(i + 1);
+            }
+            if let Some(stress) = low.max(peak).max(recovery)
+                && stress > rest
+            {
+                worsened.push((i + 1, stress));
+            }
+        }
+        let result = if !worsened.is_empty() {
+            StressResult::Positive
+        } else if inp.target_reached {
+            StressResult::Negative
+        } else {
+            StressResult::NonDiagnostic
+        };
+        Self {
+            result,
+            ischemic: by_territory(&worsened),
+            viable,
+            biphasic,
+            persistent,
+        }
+    }
+}
+
+impl RenderToString for StressEcho {
+    fn render_to_string(&self) -> String {
+        let numbers = |segments: &[usize]| {
+            segments
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let mut out = self.result.to_string();
+        if !self.ischemic.is_empty() {
+            let territories: Vec<String> = self
+                .ischemic
+                .iter()
+                .map(|(territory, segments)| {
+                    format!("в бассейне {} ({})", territory, describe_segments(segments))
+                })
+                .collect();
+            out.push_str(&format!(" {}", territories.join(", ")));
+        }
+        out.push('.');
+        if !self.viable.is_empty() {
+            out.push_str(&format!(
+                " Улучшение сократимости на низкой дозе (жизнеспособный миокард) в сегментах {}.",
+                numbers(&self.viable)
+            ));
+        }
+        if !self.biphasic.is_empty() {
+            out.push_str(&format!(
+                " Двухфазный ответ (улучшение на низкой дозе, ухудшение на пике — жизнеспособный ишемизированный миокард) в сегментах {}.",
+                numbers(&self.biphasic)
+            ));
+        }
+        if !self.persistent.is_empty() {
+            out.push_str(&format!(
+                " Нарушения сократимости сохраняются в восстановительном периоде в сегментах {}.",
+                numbers(&self.persistent)
+            ));
+        }
+        out
+    }
+}
+// стресс-ЭхоКГ конец
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // диастолическая функция начало
+    // ФВ, E, A, e' sept, e' lat, ИОЛП, V max ТР
+    fn diastolic(
+        ef: f64,
+        e: f64,
+        a: Option<f64>,
+        e_sept: f64,
+        e_lat: f64,
+        lavi: f64,
+        tr: f64,
+    ) -> DiastolicGrade {
+        DiastolicFunction::assess(&DiastolicInput {
+            peak_e: Some(e),
+            peak_a: a,
+            e_sept: Some(e_sept),
+            e_lat: Some(e_lat),
+            left_atrium_index: Some(lavi),
+            tr_velocity: Some(tr),
+            deceleration_time: None,
+            valsalva_e_a_change: None,
+            pv_s_d_ratio: None,
+            ar_a_duration: None,
+            ejection_fraction: Some(ef),
+            myocardial_disease: false,
+        })
+        .grade
+    }
+
+    #[test]
+    fn diastolic_grades() {
+        use DiastolicGrade::*;
+        let cases = [
+            // сохранная ФВ, все критерии алгоритма 1 отрицательные
+            ((60.0, 80.0, Some(70.0), 9.0, 12.0, 28.0, 2.4), Normal),
+            // 2 из 4 — неопределенная
+            (
+                (60.0, 80.0, Some(70.0), 6.0, 12.0, 30.0, 3.0),
+                Indeterminate,
+            ),
+            // 4 из 4, затем алгоритм 2: три основных критерия положительны
+            ((60.0, 100.0, Some(80.0), 5.0, 8.0, 40.0, 3.0), Grade2),
+            // сниженная ФВ: E/A ≤ 0,8 и E ≤ 50
+            ((40.0, 45.0, Some(70.0), 6.0, 8.0, 30.0, 2.5), Grade1),
+            // сниженная ФВ: E/A ≥ 2
+            ((40.0, 120.0, Some(50.0), 6.0, 8.0, 40.0, 3.0), Grade3),
+            // сниженная ФВ, промежуточное E/A, основные критерии отрицательны
+            ((40.0, 70.0, Some(70.0), 8.0, 10.0, 30.0, 2.5), Grade1),
+            // сниженная ФВ без волны А
+            ((40.0, 70.0, None, 8.0, 10.0, 30.0, 2.5), Indeterminate),
+        ];
+        for ((ef, e, a, sept, lat, lavi, tr), expected) in cases {
+            assert_eq!(
+                diastolic(ef, e, a, sept, lat, lavi, tr),
+                expected,
+                "ФВ {ef}, E {e}, A {a:?}, e' {sept}/{lat}, ИОЛП {lavi}, ТР {tr}"
+            );
+        }
+    }
+
+    #[test]
+    fn valsalva_relative_decrease() {
+        // E/A 2,0 → 1,4: снижение на 30 %, не 50 %
+        assert!(!valsalva_positive(2.0, 0.6));
+        assert!(valsalva_positive(2.0, 1.0));
+        assert!(valsalva_positive(1.2, 0.6));
+        assert!(!valsalva_positive(0.0, 0.6));
+
+        // сниженная ФВ, E/A 1,2; основных критериев нет, DT < 160 мс положителен
+        let grade = |change: f64| {
+            DiastolicFunction::assess(&DiastolicInput {
+                peak_e: Some(60.0),
+                peak_a: Some(50.0),
+                e_sept: None,
+                e_lat: None,
+                left_atrium_index: None,
+                tr_velocity: None,
+                deceleration_time: Some(150.0),
+                valsalva_e_a_change: Some(change),
+                pv_s_d_ratio: None,
+                ar_a_duration: None,
+                ejection_fraction: Some(40.0),
+                myocardial_disease: false,
+            })
+            .grade
+        };
+        assert_eq!(grade(0.6), DiastolicGrade::Grade2);
+        assert_eq!(grade(0.4), DiastolicGrade::Indeterminate);
+    }
+    // диастолическая функция конец
+
+    // давление в ПП начало
+    #[test]
+    fn right_atrium_pressure() {
+        // (диаметр НПВ, коллабирование > 50 %, ДПП)
+        let cases = [
+            (1.8, true, 3),
+            (1.8, false, 8),
+            (2.1, true, 3),
+            (2.4, true, 8),
+            (2.4, false, 15),
+        ];
+        for (diameter, collapses, expected) in cases {
+            assert_eq!(
+                estimate_right_atrium_pressure(diameter, collapses),
+                expected,
+                "НПВ {diameter}, коллабирование {collapses}"
+            );
+        }
+    }
+    // давление в ПП конец
+
+    // вероятность легочной гипертензии начало
+    fn ph_input(tr_velocity: f64) -> PhInput {
+        PhInput {
+            tr_velocity,
+            right_ventricle_baz: 3.5,
+            left_ventricle_baz: Some(4.5),
+            septum_flattening: false,
+            tapse: Some(2.2),
+            systolic_pressure: 30.0,
+            rvot_notching: false,
+            pr_velocity: None,
+            pulmonary_artery: 2.2,
+            vena: 1.8,
+            ivc_collapses_over_50: true,
+            right_atrium_s: 15.0,
+        }
+    }
+
+    #[test]
+    fn ph_probability() {
+        use PhProbability::*;
+        // признаки двух категорий: A (уплощение МЖП) и B (диаметр ЛА)
+        let two_categories = |tr| PhInput {
+            septum_flattening: true,
+            pulmonary_artery: 2.8,
+            ..ph_input(tr)
+        };
+        // два признака одной категории A не дают «других признаков»
+        let one_category = |tr| PhInput {
+            septum_flattening: true,
+            right_ventricle_baz: 5.0,
+            ..ph_input(tr)
+        };
+        let cases = [
+            (ph_input(2.5), Low),
+            (one_category(2.5), Low),
+            (two_categories(2.5), Intermediate),
+            (ph_input(3.0), Intermediate),
+            (two_categories(3.0), High),
+            (ph_input(3.5), High),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(
+                PulmonaryHypertension::assess(&inp).probability,
+                expected,
+                "{inp:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ph_ivc_sign_needs_reduced_collapse() {
+        // расширенная НПВ с коллабированием > 50 % признаком не считается
+        let collapsing = PhInput {
+            vena: 2.5,
+            ivc_collapses_over_50: true,
+            pulmonary_artery: 2.8,
+            ..ph_input(2.5)
+        };
+        let assessed = PulmonaryHypertension::assess(&collapsing);
+        assert_eq!(assessed.probability, PhProbability::Low);
+
+        let plethoric = PhInput {
+            ivc_collapses_over_50: false,
+            ..collapsing
+        };
+        let assessed = PulmonaryHypertension::assess(&plethoric);
+        assert_eq!(assessed.probability, PhProbability::Intermediate);
+        assert!(
+            assessed
+                .signs
+                .contains(&"НПВ > 2,1 см с коллабированием < 50 %")
+        );
+    }
+    // вероятность легочной гипертензии конец
+
+    // аортальный стеноз начало
+    fn as_input(max_velocity: f64) -> AsInput {
+        AsInput {
+            max_velocity,
+            mid_grad: None,
+            valve_area: None,
+            leaflets_changed: true,
+            ejection_fraction: Some(60.0),
+            stroke_volume_index: Some(40.0),
+        }
+    }
+
+    #[test]
+    fn aortic_stenosis_grades() {
+        use AsGrade::*;
+        let cases = [
+            (
+                AsInput {
+                    leaflets_changed: false,
+                    ..as_input(2.0)
+                },
+                None,
+            ),
+            (as_input(2.0), Sclerosis),
+            (as_input(2.6), Mild),
+            (as_input(3.0), Moderate),
+            (as_input(4.0), Severe),
+            // градиент повышает степень, определенную по скорости
+            (
+                AsInput {
+                    mid_grad: Some(20.0),
+                    ..as_input(2.8)
+                },
+                Moderate,
+            ),
+            (
+                AsInput {
+                    mid_grad: Some(40.0),
+                    ..as_input(3.5)
+                },
+                Severe,
+            ),
+            // площадь повышает степень, определенную по скорости и градиенту
+            (
+                AsInput {
+                    valve_area: Some(1.3),
+                    ..as_input(2.7)
+                },
+                Moderate,
+            ),
+            (
+                AsInput {
+                    valve_area: Some(0.8),
+                    ..as_input(2.4)
+                },
+                Severe,
+            ),
+            // большая площадь степень не снижает и стеноз не создает
+            (
+                AsInput {
+                    valve_area: Some(1.8),
+                    ..as_input(3.2)
+                },
+                Moderate,
+            ),
+            (
+                AsInput {
+                    valve_area: Some(2.5),
+                    ..as_input(2.0)
+                },
+                Sclerosis,
+            ),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(AorticStenosis::assess(&inp).grade, expected, "{inp:?}");
+        }
+    }
+
+    #[test]
+    fn aortic_stenosis_low_gradient() {
+        use LowGradient::*;
+        let small = |ef, svi| AsInput {
+            valve_area: Some(0.8),
+            ejection_fraction: Some(ef),
+            stroke_volume_index: Some(svi),
+            ..as_input(3.5)
+        };
+        let cases = [
+            (small(40.0, 30.0), Some(Classical)),
+            (small(60.0, 30.0), Some(Paradoxical)),
+            (small(60.0, 40.0), Some(NormalFlow)),
+            // площадь ≥ 1,0 см²
+            (
+                AsInput {
+                    valve_area: Some(1.2),
+                    ..small(60.0, 40.0)
+                },
+                Option::None,
+            ),
+            // скорость ниже порога незначительного стеноза — оговорка сохраняется
+            (
+                AsInput {
+                    max_velocity: 2.4,
+                    ..small(40.0, 30.0)
+                },
+                Some(Classical),
+            ),
+            // тяжелый стеноз по скорости — без оговорки о низком градиенте
+            (
+                AsInput {
+                    max_velocity: 4.5,
+                    ..small(60.0, 40.0)
+                },
+                Option::None,
+            ),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(
+                AorticStenosis::assess(&inp).low_gradient,
+                expected,
+                "{inp:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn aortic_stenosis_low_flow_below_mild() {
+        // V max 2,4 м/с при S 0,8 см²: не склероз, а возможный тяжелый LFLG-стеноз
+        let stenosis = AorticStenosis::assess(&AsInput {
+            valve_area: Some(0.8),
+            ejection_fraction: Some(40.0),
+            stroke_volume_index: Some(30.0),
+            ..as_input(2.4)
+        });
+        assert_eq!(stenosis.grade, AsGrade::Severe);
+        assert_eq!(stenosis.low_gradient, Some(LowGradient::Classical));
+        assert_eq!(
+            stenosis.render_to_string(),
+            " Заключение по АК: тяжелый аортальный стеноз по площади отверстия (S < 1,0 см²) \
+             при невысоком градиенте — низкопоточный низкоградиентный стеноз со сниженной ФВ, \
+             тяжесть требует подтверждения."
+        );
+    }
+    // аортальный стеноз конец
+
+    // регургитации начало
+    fn ar_input() -> ArInput {
+        ArInput {
+            presh_time: None,
+            vena_contracta: None,
+            jet_width_ratio: None,
+            holodiastolic_reversal: None,
+            eroa: None,
+            regurgitant_volume: None,
+        }
+    }
+
+    #[test]
+    fn aortic_regurgitation_grades() {
+        use RegurgitationGrade::*;
+        let cases = [
+            (ar_input(), None),
+            (
+                ArInput {
+                    presh_time: Some(600.0),
+                    ..ar_input()
+                },
+                Some(Mild),
+            ),
+            (
+                ArInput {
+                    presh_time: Some(350.0),
+                    vena_contracta: Some(0.4),
+                    ..ar_input()
+                },
+                Some(Moderate),
+            ),
+            (
+                ArInput {
+                    presh_time: Some(150.0),
+                    vena_contracta: Some(0.7),
+                    jet_width_ratio: Some(70.0),
+                    ..ar_input()
+                },
+                Some(Severe),
+            ),
+            // количественные параметры приоритетны
+            (
+                ArInput {
+                    presh_time: Some(600.0),
+                    vena_contracta: Some(0.2),
+                    eroa: Some(0.32),
+                    ..ar_input()
+                },
+                Some(Severe),
+            ),
+            (
+                ArInput {
+                    vena_contracta: Some(0.7),
+                    regurgitant_volume: Some(40.0),
+                    ..ar_input()
+                },
+                Some(Moderate),
+            ),
+            // при равенстве голосов — более тяжелая степень
+            (
+                ArInput {
+                    presh_time: Some(600.0),
+                    vena_contracta: Some(0.4),
+                    ..ar_input()
+                },
+                Some(Moderate),
+            ),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(grade_aortic_regurgitation(&inp), expected, "{inp:?}");
+        }
+    }
+
+    #[test]
+    fn absent_flow_reversal_does_not_vote() {
+        let moderate = ArInput {
+            presh_time: Some(350.0),
+            vena_contracta: Some(0.4),
+            ..ar_input()
+        };
+        // без голодиастолического потока степень определяется остальными параметрами
+        let without_reversal = ArInput {
+            holodiastolic_reversal: Some(false),
+            jet_width_ratio: Some(10.0),
+            ..moderate
+        };
+        assert_eq!(
+            grade_aortic_regurgitation(&without_reversal),
+            Some(RegurgitationGrade::Moderate)
+        );
+        assert_eq!(
+            grade_aortic_regurgitation(&ArInput {
+                holodiastolic_reversal: Some(false),
+                ..ar_input()
+            }),
+            None
+        );
+        assert_eq!(
+            grade_aortic_regurgitation(&ArInput {
+                presh_time: Some(350.0),
+                holodiastolic_reversal: Some(true),
+                ..ar_input()
+            }),
+            Some(RegurgitationGrade::Severe)
+        );
+    }
+
+    #[test]
+    fn mitral_regurgitation_grades() {
+        use RegurgitationGrade::*;
+        let mr = |vena_contracta, eroa, regurgitant_volume| MrInput {
+            vena_contracta,
+            eroa,
+            regurgitant_volume,
+        };
+        let cases = [
+            (mr(None, None, None), None),
+            (mr(Some(0.2), None, None), Some(Mild)),
+            (mr(Some(0.5), None, None), Some(Moderate)),
+            (mr(Some(0.7), None, None), Some(Severe)),
+            (mr(Some(0.7), Some(0.25), None), Some(Moderate)),
+            (mr(Some(0.2), Some(0.15), Some(65.0)), Some(Severe)),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(grade_mitral_regurgitation(&inp), expected, "{inp:?}");
+        }
+    }
+    // регургитации конец
+
+    // стенозы МК и ЛА начало
+    #[test]
+    fn mitral_stenosis_grades() {
+        use StenosisGrade::{Mild, Moderate, Severe};
+        let ms = |valve_area, mid_grad, systolic_pressure| MsInput {
+            mid_grad,
+            valve_area,
+            systolic_pressure,
+        };
+        let cases = [
+            (ms(None, None, 25.0), StenosisGrade::None),
+            (ms(Some(2.5), Some(3.0), 25.0), StenosisGrade::None),
+            // по площади
+            (ms(Some(1.8), Some(3.0), 25.0), Mild),
+            (ms(Some(1.5), Some(3.0), 25.0), Moderate),
+            (ms(Some(0.9), Some(3.0), 25.0), Severe),
+            // площадь приоритетна: СДЛА не повышает степень
+            (ms(Some(1.2), Some(8.0), 60.0), Moderate),
+            // без площади — по среднему градиенту; нормальный клапан — не стеноз
+            (ms(None, Some(3.0), 25.0), StenosisGrade::None),
+            (ms(None, Some(5.0), 25.0), Moderate),
+            (ms(None, Some(12.0), 25.0), Severe),
+            // СДЛА > 50 при умеренном градиенте
+            (ms(None, Some(8.0), 55.0), Severe),
+            (ms(None, Some(8.0), 45.0), Moderate),
+            // высокая СДЛА без стенотического градиента степень не меняет
+            (ms(None, Some(3.0), 60.0), StenosisGrade::None),
+        ];
+        for (inp, expected) in cases {
+            assert_eq!(MitralStenosis::assess(&inp).grade, expected, "{inp:?}");
+        }
+    }
+
+    #[test]
+    fn mitral_stenosis_text() {
+        let ms = MitralStenosis::assess(&MsInput {
+            mid_grad: Some(8.0),
+            valve_area: Some(1.2),
+            systolic_pressure: 40.0,
+        });
+        assert_eq!(
+            ms.render_to_string(),
+            " Признаки митрального стеноза умеренной степени \
+             (S МК 1,2 см², Gr ср 8,0 мм рт.ст., СДЛА 40 мм рт.ст.)."
+        );
+    }
+
+    #[test]
+    fn pulmonary_stenosis_grades() {
+        use StenosisGrade::{Mild, Moderate, Severe};
+        let cases = [
+            (10.0, StenosisGrade::None),
+            (16.0, Mild),
+            (35.0, Mild),
+            (36.0, Moderate),
+            (64.0, Moderate),
+            (65.0, Severe),
+        ];
+        for (max_grad, expected) in cases {
+            assert_eq!(
+                PulmonaryStenosis::assess(max_grad).grade,
+                expected,
+                "Gr max {max_grad}"
+            );
+        }
+    }
+
+    #[test]
+    fn pulmonary_stenosis_text() {
+        assert_eq!(PulmonaryStenosis::assess(10.0).render_to_string(), "");
+        assert_eq!(
+            PulmonaryStenosis::assess(40.0).render_to_string(),
+            " Признаки стеноза ЛА умеренной степени (Gr max 40 мм рт.ст.)."
+        );
+    }
+    // стенозы МК и ЛА конец
+
+    // протезы клапанов начало
+    fn aortic_prosthesis(mechanical: bool, size: f64, eoa: f64) -> AorticProsthesisInput {
+        AorticProsthesisInput {
+            mechanical,
+            size,
+            max_velocity: 2.5,
+            mid_grad: Option::None,
+            dvi: Option::None,
+            eoa: Some(eoa),
+            eoa_index: Option::None,
+            acceleration_time: Option::None,
+        }
+    }
+
+    #[test]
+    fn reference_eoa_by_type_and_size() {
+        assert_eq!(reference_eoa(true, 19.0), Some(1.0));
+        assert_eq!(reference_eoa(false, 25.0), Some(1.7));
+        assert_eq!(reference_eoa(true, 20.0), Option::None);
+    }
+
+    #[test]
+    fn aortic_prosthesis_eoa_against_reference() {
+        use ProsthesisGrade::{Normal, PossibleStenosis, SignificantStenosis};
+        // (механический, размер, EOA, ожидаемая степень по EOA)
+        let cases = [
+            (true, 19.0, 1.0, Normal),
+            (false, 25.0, 1.3, PossibleStenosis),
+            (false, 25.0, 1.5, Normal),
+            (true, 23.0, 0.7, SignificantStenosis),
+            // размер вне таблицы — общие пороги ASE
+            (true, 20.0, 1.0, PossibleStenosis),
+        ];
+        for (mechanical, size, eoa, expected) in cases {
+            // при нормальной скорости и равенстве голосов решает EOA
+            let inp = aortic_prosthesis(mechanical, size, eoa);
+            assert_eq!(
+                AorticProsthesisFunction::assess(&inp).grade,
+                expected,
+                "{mechanical} {size} мм, EOA {eoa}"
+            );
+        }
+    }
+
+    #[test]
+    fn aortic_prosthesis_mismatch() {
+        let cases = [
+            (0.6, PatientProsthesisMismatch::Severe),
+            (0.8, PatientProsthesisMismatch::Moderate),
+            (0.9, PatientProsthesisMismatch::None),
+        ];
+        for (index, expected) in cases {
+            let mut inp = aortic_prosthesis(false, 21.0, 1.3);
+            inp.eoa_index = Some(index);
+            assert_eq!(
+                AorticProsthesisFunction::assess(&inp).mismatch,
+                Some(expected),
+                "EOAi {index}"
+            );
+        }
+    }
+
+    fn mitral_prosthesis(peak_e: f64, mid_grad: f64, pht: f64) -> AvProsthesisInput {
+        AvProsthesisInput {
+            peak_e,
+            mid_grad,
+            presh_time: Some(pht),
+            vti_ratio: Option::None,
+            significant_leak: false,
+        }
+    }
+
+    #[test]
+    fn mitral_prosthesis_grades() {
+        use ProsthesisGrade::{Normal, PossibleStenosis, Regurgitation, SignificantStenosis};
+        // (E, Gr ср, PHT, фистула, ожидаемая степень)
+        let cases = [
+            (1.5, 4.0, 100.0, false, Normal),
+            (2.0, 7.0, 150.0, false, PossibleStenosis),
+            (2.6, 12.0, 220.0, false, SignificantStenosis),
+            // высокие скорости при коротком PHT — регургитация, а не норма
+            (2.0, 7.0, 100.0, false, Regurgitation),
+            (1.5, 4.0, 100.0, true, Regurgitation),
+            // стеноз с фистулой остается стенозом, фистула описывается отдельно
+            (2.6, 12.0, 220.0, true, SignificantStenosis),
+        ];
+        for (peak_e, mid_grad, pht, leak, expected) in cases {
+            let mut inp = mitral_prosthesis(peak_e, mid_grad, pht);
+            inp.significant_leak = leak;
+            let function = AvProsthesisFunction::assess_mitral(&inp);
+            assert_eq!(
+                function.grade, expected,
+                "E {peak_e}, Gr {mid_grad}, PHT {pht}"
+            );
+            // дисфункция не отрицается при подозрении на регургитацию
+            assert!(!(function.regurgitation_suspected && function.grade == Normal));
+        }
+    }
+
+    #[test]
+    fn tricuspid_prosthesis_grades() {
+        use ProsthesisGrade::{Normal, PossibleStenosis, Regurgitation, SignificantStenosis};
+        let cases = [
+            (1.2, 3.0, 150.0, false, Normal),
+            (1.8, 3.0, 150.0, false, PossibleStenosis),
+            (1.8, 7.0, 250.0, false, SignificantStenosis),
+            (1.2, 3.0, 150.0, true, Regurgitation),
+        ];
+        for (peak_e, mid_grad, pht, leak, expected) in cases {
+            let mut inp = mitral_prosthesis(peak_e, mid_grad, pht);
+            inp.significant_leak = leak;
+            assert_eq!(
+                AvProsthesisFunction::assess_tricuspid(&inp).grade,
+                expected,
+                "E {peak_e}, Gr {mid_grad}, PHT {pht}"
+            );
+        }
+    }
+    // протезы клапанов конец
+
+    // размеры аорты начало
+    fn aorta(sinus_diameter: f64, ascending_diameter: f64, age: f64, male: bool) -> AortaInput {
+        AortaInput {
+            sinus_diameter,
+            ascending_diameter,
+            age,
+            body_surface_area: 2.0,
+            height: 1.75,
+            male,
+        }
+    }
+
+    #[test]
+    fn aortic_sinus_z_score() {
+        // должный диаметр: 2,423 + 0,009 · 50 + 0,461 · 2,0 − 0,267 = 3,528 см
+        let size = AorticSize::assess(&aorta(3.528, 3.0, 50.0, true));
+        assert!((size.sinus_expected - 3.528).abs() < 1e-9);
+        assert!(size.sinus_z.abs() < 1e-9);
+        assert!((size.ascending_index - 1.5).abs() < 1e-9);
+
+        // (диаметр, возраст, мужской пол, расширение)
+        let cases = [
+            (3.5, 50.0, true, false),
+            (4.2, 50.0, true, true),
+            (4.0, 50.0, false, true),
+            (4.0, 80.0, true, false),
+        ];
+        for (diameter, age, male, dilated) in cases {
+            let size = AorticSize::assess(&aorta(diameter, 3.0, age, male));
+            assert_eq!(size.sinus_dilated(), dilated, "Ао {diameter}, {age} лет");
+        }
+    }
+
+    #[test]
+    fn aortic_ascending_z_score() {
+        // должный диаметр: 1,35 + 0,011 · 50 + 0,58 · 2,0 = 3,06 см, SEE 0,30
+        let size = AorticSize::assess(&aorta(3.5, 3.66, 50.0, true));
+        assert!((size.ascending_expected - 3.06).abs() < 1e-9);
+        assert!((size.ascending_z - 2.0).abs() < 1e-9);
+
+        // (диаметр, возраст, мужской пол, расширение): должный растет с возрастом
+        let cases = [
+            (3.0, 50.0, true, false),
+            (3.8, 50.0, true, true),
+            (3.8, 80.0, true, false),
+            (3.5, 50.0, false, true),
+            (3.2, 50.0, false, false),
+        ];
+        for (diameter, age, male, dilated) in cases {
+            let size = AorticSize::assess(&aorta(3.5, diameter, age, male));
+            assert_eq!(
+                size.ascending_dilated(),
+                dilated,
+                "ВА {diameter}, {age} лет"
+            );
+        }
+    }
+
+    #[test]
+    fn aortic_dilation_text() {
+        let text = |sinus, ascending| {
+            AorticSize::assess(&aorta(sinus, ascending, 50.0, true)).render_to_string()
+        };
+        assert_eq!(text(3.5, 3.0), "");
+        assert_eq!(
+            text(4.2, 3.0),
+            " Расширение аорты на уровне синусов Вальсальвы (z > 2)."
+        );
+        assert_eq!(
+            text(3.5, 3.8),
+            " Расширение восходящего отдела аорты (z > 2)."
+        );
+        assert_eq!(
+            text(4.2, 3.8),
+            " Расширение аорты на уровне синусов Вальсальвы и восходящего отдела (z > 2)."
+        );
+    }
+    // размеры аорты конец
+
+    // функция ПЖ начало
+    fn rv_input() -> RvInput {
+        RvInput {
+            end_diastolic_area: Some(20.0),
+            end_systolic_area: Some(10.0),
+            s_prime: Some(12.0),
+            rimp: Some(0.4),
+            tapse: Some(2.2),
+            systolic_pressure: 30.0,
+        }
+    }
+
+    #[test]
+    fn rv_function() {
+        let rv = RvFunction::assess(&rv_input());
+        assert_eq!(rv.fac, Some(50.0));
+        assert!(!rv.is_impaired());
+        assert!(!rv.is_uncoupled());
+
+        // каждый сниженный показатель по отдельности — систолическая дисфункция ПЖ
+        let impaired: [fn(&mut RvInput); 3] = [
+            |i| i.end_systolic_area = Some(14.0),
+            |i| i.s_prime = Some(9.0),
+            |i| i.rimp = Some(0.6),
+        ];
+        for (n, change) in impaired.iter().enumerate() {
+            let mut inp = rv_input();
+            change(&mut inp);
+            assert!(RvFunction::assess(&inp).is_impaired(), "случай {n}");
+        }
+    }
+
+    #[test]
+    fn rv_coupling() {
+        // (TAPSE, СДЛА, разобщение): TAPSE в см переводится в мм
+        let cases = [(2.2, 30.0, false), (1.5, 30.0, true), (1.6, 29.0, false)];
+        for (tapse, spap, uncoupled) in cases {
+            let mut inp = rv_input();
+            inp.tapse = Some(tapse);
+            inp.systolic_pressure = spap;
+            assert_eq!(
+                RvFunction::assess(&inp).is_uncoupled(),
+                uncoupled,
+                "TAPSE {tapse}, СДЛА {spap}"
+            );
+        }
+
+        let mut inp = rv_input();
+        inp.systolic_pressure = 0.0;
+        assert_eq!(RvFunction::assess(&inp).tapse_spap, Option::None);
+    }
+    // функция ПЖ конец
+
+    // деформация (speckle tracking) начало
+    #[test]
+    fn strain_grades() {
+        use StrainGrade::{Borderline, Normal, Reduced};
+        let cases = [
+            (-20.0, Normal),
+            (-18.0, Normal),
+            (-17.0, Borderline),
+            (-16.0, Borderline),
+            (-12.0, Reduced),
+            (5.0, Reduced),
+        ];
+        for (strain, expected) in cases {
+            assert_eq!(
+                StrainGrade::of_left_ventricle(strain),
+                expected,
+                "{strain} %"
+            );
+        }
+    }
+
+    #[test]
+    fn strain_sign_normalized() {
+        let mut segments = [Some(-20.0); SEGMENTS];
+        segments[3] = Some(-10.0);
+        // положительная региональная деформация — дискинез, а не норма
+        segments[6] = Some(4.0);
+        let inp = StrainInput {
+            gls: 19.0,
+            segments: Some(segments),
+            rv_free_wall: Some(22.0),
+            la_reservoir: Some(15.0),
+        };
+        let function = StrainFunction::assess(&inp);
+        assert_eq!(function.gls, StrainGrade::Normal);
+        assert_eq!(function.rv_reduced, Some(false));
+        assert_eq!(function.la_reduced, Some(true));
+        assert_eq!(function.reduced_segments, vec![4, 7]);
+
+        let function = StrainFunction::assess(&StrainInput {
+            gls: -14.0,
+            segments: Option::None,
+            rv_free_wall: Some(-15.0),
+            la_reservoir: Option::None,
+        });
+        assert_eq!(function.gls, StrainGrade::Reduced);
+        assert_eq!(function.rv_reduced, Some(true));
+        assert_eq!(function.la_reduced, Option::None);
+    }
+    // деформация (speckle tracking) конец
+
+    // локальная сократимость ЛЖ начало
+    #[test]
+    fn coronary_territories() {
+        use CoronaryTerritory::{Lad, Lcx, Rca};
+        let expected = [
+            Lad, Lad, Rca, Rca, Lcx, Lcx, // базальные
+            Lad, Lad, Rca, Rca, Lcx, Lcx, // средние
+            Lad, Lad, Rca, Lcx, // верхушечные
+            Lad, // верхушка
+        ];
+        for (i, territory) in expected.into_iter().enumerate() {
+            assert_eq!(
+                CoronaryTerritory::of_segment(i + 1),
+                Some(territory),
+                "сегмент {}",
+                i + 1
+            );
+        }
+        assert_eq!(CoronaryTerritory::of_segment(0), Option::None);
+        assert_eq!(CoronaryTerritory::of_segment(18), Option::None);
+    }
+
+    #[test]
+    fn wall_motion_index() {
+        use crate::promptget::NotCalculated;
+        let mut scores: WallMotionScores = [Some(WallMotion::Normal); SEGMENTS];
+        let normal = WallMotionAnalysis::assess(&scores);
+        assert_eq!(normal.wmsi, Ok(1.0));
+        assert!(!normal.has_abnormalities());
+
+        scores[6] = Some(WallMotion::Akinesis);
+        scores[15] = Some(WallMotion::Hypokinesis);
+        scores[16] = Option::None;
+        let analysis = WallMotionAnalysis::assess(&scores);
+        // (14 · 1 + 3 + 2) / 16
+        assert_eq!(analysis.wmsi, Ok(19.0 / 16.0));
+        assert_eq!(analysis.not_visualized, 1);
+        assert_eq!(
+            analysis.affected,
+            vec![
+                (CoronaryTerritory::Lad, vec![(7, WallMotion::Akinesis)]),
+                (CoronaryTerritory::Lcx, vec![(16, WallMotion::Hypokinesis)]),
+            ]
+        );
+
+        let none = WallMotionAnalysis::assess(&[Option::None; SEGMENTS]);
+        assert_eq!(
+            none.wmsi,
+            Err(NotCalculated::Zero("число визуализированных сегментов"))
+        );
+    }
+    // локальная сократимость ЛЖ конец
+
+    // стресс-ЭхоКГ начало
+    fn with_scores(changes: &[(usize, WallMotion)]) -> WallMotionScores {
+        let mut scores = [Some(WallMotion::Normal); SEGMENTS];
+        for &(n, m) in changes {
+            scores[n - 1] = Some(m);
+        }
+        scores
+    }
+
+    #[test]
+    fn stress_results() {
+        use WallMotion::{Akinesis, Hypokinesis};
+        let normal = with_scores(&[]);
+        let ischemia = with_scores(&[(7, Hypokinesis)]);
+        // (низкая доза, пик, восстановление, целевая ЧСС, результат)
+        let cases = [
+            (Option::None, &normal, &normal, true, StressResult::Negative),
+            (
+                Option::None,
+                &normal,
+                &normal,
+                false,
+                StressResult::NonDiagnostic,
+            ),
+            (
+                Option::None,
+                &ischemia,
+                &normal,
+                false,
+                StressResult::Positive,
+            ),
+            (
+                Some(&ischemia),
+                &normal,
+                &normal,
+                true,
+                StressResult::Positive,
+            ),
+            (
+                Option::None,
+                &normal,
+                &ischemia,
+                true,
+                StressResult::Positive,
+            ),
+        ];
+        for (n, (low_dose, peak, recovery, target_reached, expected)) in
+            cases.into_iter().enumerate()
+        {
+            let echo = StressEcho::assess(&StressInput {
+                rest: &normal,
+                low_dose,
+                peak,
+                recovery,
+                target_reached,
+            });
+            assert_eq!(echo.result, expected, "случай {n}");
+        }
+
+        // акинез в покое без изменений — не ишемия
+        let rest = with_scores(&[(4, Akinesis)]);
+        let echo = StressEcho::assess(&StressInput {
+            rest: &rest,
+            low_dose: Option::None,
+            peak: &rest,
+            recovery: &rest,
+            target_reached: true,
+        });
+        assert_eq!(echo.result, StressResult::Negative);
+        assert!(echo.persistent.is_empty());
+    }
+
+    #[test]
+    fn stress_viability_patterns() {
+        use WallMotion::{Akinesis, Hypokinesis, Normal};
+        // 4: стойкое улучшение; 10: двухфазный ответ; 7: ухудшение, сохраняется в восстановлении
+        let rest = with_scores(&[(4, Akinesis), (10, Hypokinesis)]);
+        let low_dose = with_scores(&[(4, Hypokinesis), (10, Normal)]);
+        let peak = with_scores(&[(4, Normal), (10, Hypokinesis), (7, Akinesis)]);
+        let recovery = with_scores(&[(4, Hypokinesis), (10, Hypokinesis), (7, Hypokinesis)]);
+        let echo = StressEcho::assess(&StressInput {
+            rest: &rest,
+            low_dose: Some(&low_dose),
+            peak: &peak,
+            recovery: &recovery,
+            target_reached: true,
+        });
+        assert_eq!(echo.result, StressResult::Positive);
+        assert_eq!(echo.viable, vec![4]);
+        assert_eq!(echo.biphasic, vec![10]);
+        assert_eq!(echo.persistent, vec![7]);
+        assert_eq!(
+            echo.ischemic,
+            vec![
+                (CoronaryTerritory::Lad, vec![(7, Akinesis)]),
+                (CoronaryTerritory::Rca, vec![(10, Hypokinesis)]),
+            ]
+        );
+    }
+    // стресс-ЭхоКГ конец
+}
@@ -1,30 +1,50 @@
+mod bullseye;
+mod grading;
 mod promptget;
 mod report;
 mod reporttypes;
 mod settings;
+mod stress;
 use chrono::{DateTime, Local};
+use promptget::ask_selection;
 use reporttypes::{CalculatedReportData, RawReportData};
 use serde_json::Value;
 use settings::get_exe_dir;
 use settings::load_settings;
 use std::fs;
+use stress::{ProtocolMode, StressCalculatedData, StressRawData};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cur_settings = load_settings();
     let today: DateTime<Local> = Local::now();
 
-    let raw_report = RawReportData::gather();
-    let calculated_report = CalculatedReportData::from_raw(&raw_report, today);
-    let rendered_report = calculated_report.render();
+    let mode: ProtocolMode = ask_selection("Протокол");
+    let (name, data, tplt_name): (String, Value, &str) = if mode.is_stress() {
+        let raw_report = StressRawData::gather();
+        let calculated_report = StressCalculatedData::from_raw(&raw_report, today);
+        let rendered_report = calculated_report.render();
+        (
+            calculated_report.name,
+            serde_json::to_value(&rendered_report)?,
+            "stress_tplt.docx",
+        )
+    } else {
+        let raw_report = RawReportData::gather(cur_settings.get_bsa_formula());
+        let calculated_report = CalculatedReportData::from_raw(&raw_report, today);
+        for warning in calculated_report.review_warnings() {
+            println!("Внимание: {}", warning);
+        }
+        let rendered_report = calculated_report.render();
+        (
+            calculated_report.name,
+            serde_json::to_value(&rendered_report)?,
+            "tplt.docx",
+        )
+    };
 
-    let out_filename: String = format!(
-        "{} {}.docx",
-        &calculated_report.name,
-        today.format("%y%m%d")
-    );
+    let out_filename: String = format!("{} {}.docx", &name, today.format("%y%m%d"));
 
-    let tplt_loc = get_exe_dir().join("assets").join("tplt.docx");
-    let data: Value = serde_json::to_value(&rendered_report)?;
+    let tplt_loc = get_exe_dir().join("assets").join(tplt_name);
 
     let template_bytes = fs::read(tplt_loc)?;
     let rendered_bytes = docx_handlebars::render_template(template_bytes, &data)?;
@@ -1,8 +1,55 @@
+use crate::bullseye::SEGMENTS;
 use chrono::{DateTime, Datelike, Local, NaiveDate};
 use inquire::{InquireError, Select, Text};
 use std::{fmt, process};
 
 // типы начало
+// значения отдельных циклов одного измерения; PreciseNum хранит только среднее
+#[derive(Debug, Clone)]
+pub struct Beats {
+    pub label: String,
+    pub values: Vec<f64>,
+    pub precision: u8,
+}
+
+impl Beats {
+    pub fn mean(&self) -> f64 {
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    // выборочное стандартное отклонение, для одного цикла не считается
+    pub fn sd(&self) -> Option<f64> {
+        let n = self.values.len();
+        if n < 2 {
+            return None;
+        }
+        let mean = self.mean();
+        let sum_sq: f64 = self.values.iter().map(|v| (v - mean).powi(2)).sum();
+        Some((sum_sq / (n - 1) as f64).sqrt())
+    }
+}
+
+impl RenderToString for Beats {
+    // "ЧСС — среднее из 3 циклов (81; 79; 85; SD 3)"
+    fn render_to_string(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|v| PreciseNum::from_float(*v, self.precision).to_string())
+            .collect();
+        format!(
+            "{} — среднее из {} циклов ({}{})",
+            self.label,
+            self.values.len(),
+            values.join("; "),
+            match self.sd() {
+                Some(sd) => format!("; SD {}", PreciseNum::from_float(sd, self.precision)),
+                None => "".to_owned(),
+            }
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct PreciseNum {
     value: f64,
@@ -11,7 +58,7 @@ pub struct PreciseNum {
 
 #[derive(Debug, Clone)]
 pub struct AutoValue {
-    pub value: PreciseNum,
+    pub value: Derived,
     pub auto: bool,
 }
 
@@ -22,10 +69,34 @@ impl fmt::Display for AutoValue {
         } else {
             " (по допплеру)"
         };
-        write!(f, "{} мл{}", self.value, v)
+        write!(f, "{} мл{}", render_derived(self.value, 0), v)
+    }
+}
+
+// почему производная величина не рассчитана
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotCalculated {
+    // исходная величина не измерялась
+    Missing,
+    // исходная величина (делитель) равна нулю
+    Zero(&'static str),
+    // результат не является конечным числом
+    NotFinite,
+}
+
+impl fmt::Display for NotCalculated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotCalculated::Missing => write!(f, "нет исходных данных"),
+            NotCalculated::Zero(what) => write!(f, "{} равно нулю", what),
+            NotCalculated::NotFinite => write!(f, "некорректный результат"),
+        }
     }
 }
 
+// производная величина: значение или причина, по которой она не рассчитана
+pub type Derived = Result<f64, NotCalculated>;
+
 pub trait RenderToString {
     fn render_to_string(&self) -> String;
 }
@@ -66,6 +137,11 @@ pub enum ParseError {
     NumXNumSecondInvalid,
     NumXNumTooMany,
     EmptyString,
+    SegmentsCount,
+    WallMotionFormat,
+    WallMotionScore,
+    SegmentNumber,
+    BloodPressureFormat,
 }
 
 impl fmt::Display for ParseError {
@@ -79,11 +155,33 @@ impl fmt::Display for ParseError {
             ParseError::NumXNumSecondInvalid => "Второе число некорректно.",
             ParseError::NumXNumTooMany => "Должно быть ровно два числа, введено более двух.",
             ParseError::EmptyString => "Строка не должна быть пустой.",
+            ParseError::SegmentsCount => {
+                "Нужно 17 значений через пробел (x — сегмент не визуализирован)."
+            }
+            ParseError::WallMotionFormat => {
+                "Формат: «норма кроме 7,13=2; 4=3» или 17 баллов через пробел."
+            }
+            ParseError::WallMotionScore => "Балл от 1 до 5 (x — сегмент не визуализирован).",
+            ParseError::SegmentNumber => "Номер сегмента от 1 до 17.",
+            ParseError::BloodPressureFormat => "Введите АД как 120/80.",
         };
         write!(f, "{msg}")
     }
 }
 
+// АД, мм рт.ст.
+#[derive(Debug, Clone, Copy)]
+pub struct BloodPressure {
+    pub systolic: i64,
+    pub diastolic: i64,
+}
+
+impl fmt::Display for BloodPressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.systolic, self.diastolic)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct NumXNum {
     num1: PreciseNum,
@@ -107,6 +205,50 @@ pub fn calc_age(birthday: NaiveDate, td: DateTime<Local>) -> i32 {
     age
 }
 
+// площадь круга по диаметру, см²
+pub fn circle_area(diameter: f64) -> f64 {
+    std::f64::consts::PI * (diameter / 2.0).powi(2)
+}
+
+// объём через сечение: площадь по диаметру × VTI, мл
+pub fn vti_volume(diameter: f64, vti: f64) -> f64 {
+    circle_area(diameter) * vti
+}
+
+// объём ЛЖ по Тейхольцу: V = 7 / (2,4 + D) · D³, D в см, результат в мл
+pub fn teichholz_volume(diameter: f64) -> f64 {
+    7.0 / (2.4 + diameter) * diameter.powi(3)
+}
+
+// EROA по методу PISA: радиус в см, скорости в см/с, результат в см²
+pub fn pisa_eroa(radius: f64, aliasing_velocity: f64, peak_velocity: f64) -> Derived {
+    checked_div(
+        2.0 * std::f64::consts::PI * radius.powi(2) * aliasing_velocity,
+        peak_velocity,
+        "пиковая скорость регургитации",
+    )
+}
+
+pub fn finite(v: f64) -> Derived {
+    if v.is_finite() {
+        Ok(v)
+    } else {
+        Err(NotCalculated::NotFinite)
+    }
+}
+
+// деление с проверкой делителя; divisor — название делителя для сообщения
+pub fn checked_div(num: f64, den: f64, divisor: &'static str) -> Derived {
+    if den == 0.0 {
+        return Err(NotCalculated::Zero(divisor));
+    }
+    finite(num / den)
+}
+
+pub fn measured(v: Option<PreciseNum>) -> Derived {
+    v.map(|v| v.value()).ok_or(NotCalculated::Missing)
+}
+
 fn prep_num_msg(msg: &str, p: u8) -> String {
     if p == 0 {
         format!("{} (целое)", msg)
@@ -115,6 +257,22 @@ fn prep_num_msg(msg: &str, p: u8) -> String {
     }
 }
 
+pub fn render_derived(value: Derived, p: u8) -> String {
+    match value {
+        Ok(v) => PreciseNum::from_float(v, p).to_string(),
+        Err(_) => "—".to_owned(),
+    }
+}
+
+// как render_to_string: не измеренное опускается, не рассчитанное помечается
+pub fn render_derived_full(value: Derived, p: u8, left: &str, right: &str) -> String {
+    match value {
+        Ok(v) => format!("{}{}{}", left, PreciseNum::from_float(v, p), right),
+        Err(NotCalculated::Missing) => "".to_owned(),
+        Err(_) => format!("{}не рассчитано", left),
+    }
+}
+
 pub fn render_to_string(value: Option<PreciseNum>, left: &str, right: &str) -> String {
     match value {
         Some(v) => format!("{}{}{}", left, v, right),
@@ -235,6 +393,114 @@ pub fn parse_num_x_num(inp: &str, precision: u8) -> Result<NumXNum, ParseError>
     return Ok(NumXNum { num1, num2 });
 }
 
+// одно число или несколько циклов через пробел
+pub fn parse_num_beats(inp: &str, precision: u8) -> Result<Vec<f64>, ParseError> {
+    let values = inp
+        .split_whitespace()
+        .map(|part| parse_num_precise(part, precision).map(PreciseNum::value))
+        .collect::<Result<Vec<f64>, ParseError>>()?;
+    if values.is_empty() {
+        return Err(ParseError::EmptyNumber);
+    }
+    Ok(values)
+}
+
+// 17 сегментов по порядку AHA через пробел, x — сегмент не визуализирован
+pub fn parse_segment_values(
+    inp: &str,
+    precision: u8,
+) -> Result<[Option<PreciseNum>; SEGMENTS], ParseError> {
+    let parts: Vec<&str> = inp.split_whitespace().collect();
+    if parts.len() != SEGMENTS {
+        return Err(ParseError::SegmentsCount);
+    }
+    let mut values = [None; SEGMENTS];
+    for (value, part) in values.iter_mut().zip(parts) {
+        if !part.eq_ignore_ascii_case("x") {
+            *value = Some(parse_num_precise(part, precision)?);
+        }
+    }
+    Ok(values)
+}
+
+fn parse_wall_motion_score(inp: &str) -> Result<Option<u8>, ParseError> {
+    if inp.eq_ignore_ascii_case("x") {
+        return Ok(None);
+    }
+    match inp.parse::<u8>() {
+        Ok(score @ 1..=5) => Ok(Some(score)),
+        _ => Err(ParseError::WallMotionScore),
+    }
+}
+
+// баллы сократимости 17 сегментов (1 — норма … 5 — аневризма, x — не визуализирован):
+// «норма кроме 7,13=2; 4=3» или 17 баллов через пробел
+pub fn parse_wall_motion(inp: &str) -> Result<[Option<u8>; SEGMENTS], ParseError> {
+    let inp = inp.trim().to_lowercase();
+    let (base, exceptions) = match inp.split_once("кроме").or_else(|| inp.split_once("except"))
+    {
+        Some((base, exceptions)) => (base.trim(), exceptions),
+        None => (inp.as_str(), ""),
+    };
+
+    let mut scores = match base {
+        "норма" | "н" | "all normal" | "normal" => [Some(1); SEGMENTS],
+        _ => {
+            let parts: Vec<&str> = base.split_whitespace().collect();
+            if parts.is_empty() {
+                return Err(ParseError::WallMotionFormat);
+            }
+            if parts.len() != SEGMENTS {
+                return Err(ParseError::SegmentsCount);
+            }
+            let mut scores = [None; SEGMENTS];
+            for (score, part) in scores.iter_mut().zip(parts) {
+                *score = parse_wall_motion_score(part)?;
+            }
+            scores
+        }
+    };
+
+    for group in exceptions.split(';').filter(|g| !g.trim().is_empty()) {
+        let (segments, score) = group.split_once('=').ok_or(ParseError::WallMotionFormat)?;
+        let score = parse_wall_motion_score(score.trim())?;
+        let numbers: Vec<&str> = segments
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|n| !n.is_empty())
+            .collect();
+        if numbers.is_empty() {
+            return Err(ParseError::WallMotionFormat);
+        }
+        for n in numbers {
+            match n.parse::<usize>() {
+                Ok(n @ 1..=SEGMENTS) => scores[n - 1] = score,
+                _ => return Err(ParseError::SegmentNumber),
+            }
+        }
+    }
+    Ok(scores)
+}
+
+// «120/80» или «120 80»
+pub fn parse_blood_pressure(inp: &str) -> Result<BloodPressure, ParseError> {
+    let mut parts = inp
+        .split(|c: char| c == '/' || c.is_whitespace())
+        .filter(|p| !p.is_empty());
+    let (Some(systolic), Some(diastolic), None) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(ParseError::BloodPressureFormat);
+    };
+    let systolic = systolic
+        .parse()
+        .map_err(|_| ParseError::BloodPressureFormat)?;
+    let diastolic = diastolic
+        .parse()
+        .map_err(|_| ParseError::BloodPressureFormat)?;
+    Ok(BloodPressure {
+        systolic,
+        diastolic,
+    })
+}
+
 pub fn parse_int(inp: &str) -> Result<i64, ParseError> {
     if inp.is_empty() {
         Err(ParseError::EmptyNumber) // или отдельный EmptyInt
@@ -278,6 +544,77 @@ pub fn get_num_opt_if(cond: bool, msg: &str, p: u8) -> Option<PreciseNum> {
     return if cond { get_num_opt(msg, p) } else { None };
 }
 
+// значение — среднее по циклам; отдельные циклы возвращаются, если их больше одного
+fn averaged(msg: &str, inp: &str, p: u8) -> Result<(PreciseNum, Option<Beats>), ParseError> {
+    let beats = Beats {
+        label: msg.to_owned(),
+        values: parse_num_beats(inp, p)?,
+        precision: p,
+    };
+    let mean = PreciseNum::from_float(beats.mean(), p);
+    Ok((
+        mean,
+        if beats.values.len() > 1 {
+            Some(beats)
+        } else {
+            None
+        },
+    ))
+}
+
+fn prep_beats_msg(msg: &str, p: u8) -> String {
+    format!("{}, можно по циклам через пробел", prep_num_msg(msg, p))
+}
+
+pub fn get_num_beats(msg: &str, p: u8) -> (PreciseNum, Option<Beats>) {
+    ask_required(&prep_beats_msg(msg, p), |s| averaged(msg, s, p))
+}
+
+pub fn get_num_beats_if(cond: bool, msg: &str, p: u8) -> (Option<PreciseNum>, Option<Beats>) {
+    if cond {
+        let (value, beats) = get_num_beats(msg, p);
+        (Some(value), beats)
+    } else {
+        (None, None)
+    }
+}
+
+pub fn get_num_beats_opt(msg: &str, p: u8) -> (Option<PreciseNum>, Option<Beats>) {
+    match ask_optional(&prep_beats_msg(msg, p), |s| averaged(msg, s, p)) {
+        Some((value, beats)) => (Some(value), beats),
+        None => (None, None),
+    }
+}
+
+pub fn get_num_beats_opt_if(cond: bool, msg: &str, p: u8) -> (Option<PreciseNum>, Option<Beats>) {
+    if cond {
+        get_num_beats_opt(msg, p)
+    } else {
+        (None, None)
+    }
+}
+
+pub fn get_segment_values_opt(msg: &str, p: u8) -> Option<[Option<PreciseNum>; SEGMENTS]> {
+    ask_optional(
+        &format!(
+            "{}, 17 сегментов через пробел (x — не визуализирован)",
+            prep_num_msg(msg, p)
+        ),
+        |s| parse_segment_values(s, p),
+    )
+}
+
+pub fn get_wall_motion(msg: &str) -> [Option<u8>; SEGMENTS] {
+    ask_required(
+        &format!("{} (1-5, «норма кроме 7,13=2; 4=3» или 17 баллов)", msg),
+        parse_wall_motion,
+    )
+}
+
+pub fn get_blood_pressure(msg: &str) -> BloodPressure {
+    ask_required(&format!("{} (120/80)", msg), parse_blood_pressure)
+}
+
 pub fn get_num_x_num(msg: &str, p: u8) -> NumXNum {
     ask_required(
         &format!("{} {}", prep_num_msg(msg, p), "(2 числа через пробел)"),
@@ -285,3 +622,176 @@ pub fn get_num_x_num(msg: &str, p: u8) -> NumXNum {
     )
 }
 // обёртки-геттеры конец
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn derived_values() {
+        assert_eq!(finite(1.5), Ok(1.5));
+        assert_eq!(finite(f64::NAN), Err(NotCalculated::NotFinite));
+        assert_eq!(finite(f64::INFINITY), Err(NotCalculated::NotFinite));
+
+        assert_eq!(checked_div(3.0, 2.0, "КДР"), Ok(1.5));
+        assert_eq!(
+            checked_div(3.0, 0.0, "КДР"),
+            Err(NotCalculated::Zero("КДР"))
+        );
+        assert_eq!(
+            checked_div(f64::MAX, 0.5, "КДР"),
+            Err(NotCalculated::NotFinite)
+        );
+
+        assert_eq!(render_derived(Ok(1.26), 1), "1,3");
+        assert_eq!(render_derived(Err(NotCalculated::Missing), 1), "—");
+        assert_eq!(render_derived_full(Ok(2.0), 0, "S ", " см²"), "S 2 см²");
+        assert_eq!(
+            render_derived_full(Err(NotCalculated::Missing), 0, "S ", " см²"),
+            ""
+        );
+        assert_eq!(
+            render_derived_full(Err(NotCalculated::NotFinite), 0, "S ", " см²"),
+            "S не рассчитано"
+        );
+
+        let doppler = AutoValue {
+            value: Ok(64.4),
+            auto: false,
+        };
+        assert_eq!(doppler.to_string(), "64 мл (по допплеру)");
+        let simpson = AutoValue {
+            value: Err(NotCalculated::NotFinite),
+            auto: true,
+        };
+        assert_eq!(simpson.to_string(), "— мл");
+    }
+
+    #[test]
+    fn num_beats() {
+        let values = parse_num_beats("82 79 85", 0).unwrap();
+        assert_eq!(values, vec![82.0, 79.0, 85.0]);
+        // числа вводятся без запятой, с учетом точности
+        assert_eq!(parse_num_beats("125", 2).unwrap(), vec![1.25]);
+        assert!(matches!(
+            parse_num_beats("", 0),
+            Err(ParseError::EmptyNumber)
+        ));
+        assert!(matches!(
+            parse_num_beats("82 x", 0),
+            Err(ParseError::InvalidNumber)
+        ));
+
+        let beats = Beats {
+            label: "ЧСС".to_owned(),
+            values,
+            precision: 0,
+        };
+        assert_close(beats.mean(), 82.0);
+        assert_close(beats.sd().unwrap(), 3.0);
+        assert_eq!(
+            beats.render_to_string(),
+            "ЧСС — среднее из 3 циклов (82; 79; 85; SD 3)"
+        );
+
+        // циклы возвращаются вместе со средним, одно значение — без них
+        let (mean, beats) = averaged("ЧСС", "82 79 85", 0).unwrap();
+        assert_close(mean.value(), 82.0);
+        assert_eq!(beats.unwrap().values, vec![82.0, 79.0, 85.0]);
+        let (single, beats) = averaged("ЧСС", "82", 0).unwrap();
+        assert_close(single.value(), 82.0);
+        assert!(beats.is_none());
+    }
+
+    #[test]
+    fn segment_values() {
+        let values = parse_segment_values(
+            "-20 -18 x -15 -20 -20 -20 -20 -20 -20 -20 -20 -20 -20 -20 -20 -22",
+            0,
+        )
+        .unwrap();
+        assert_eq!(values[0].map(PreciseNum::value), Some(-20.0));
+        assert!(values[2].is_none());
+        assert_eq!(values[16].map(PreciseNum::value), Some(-22.0));
+
+        assert!(matches!(
+            parse_segment_values("-20 -18", 0),
+            Err(ParseError::SegmentsCount)
+        ));
+        let invalid = format!("{} abc", "-20 ".repeat(SEGMENTS - 1));
+        assert!(matches!(
+            parse_segment_values(&invalid, 0),
+            Err(ParseError::InvalidNumber)
+        ));
+    }
+
+    #[test]
+    fn wall_motion() {
+        let scores = parse_wall_motion("Норма кроме 7, 13=2; 4=3; 17=x").unwrap();
+        assert_eq!(scores[0], Some(1));
+        assert_eq!(scores[6], Some(2));
+        assert_eq!(scores[12], Some(2));
+        assert_eq!(scores[3], Some(3));
+        assert_eq!(scores[16], None);
+
+        let scores = parse_wall_motion("1 1 2 2 1 1 1 1 1 1 1 1 1 1 1 1 x").unwrap();
+        assert_eq!(scores[2], Some(2));
+        assert_eq!(scores[16], None);
+
+        let errors = [
+            ("", "WallMotionFormat"),
+            ("1 1 2", "SegmentsCount"),
+            ("норма кроме 7=6", "WallMotionScore"),
+            ("норма кроме 18=2", "SegmentNumber"),
+            ("норма кроме 7", "WallMotionFormat"),
+        ];
+        for (inp, expected) in errors {
+            let err = parse_wall_motion(inp).unwrap_err();
+            assert_eq!(format!("{err:?}"), expected, "{inp}");
+        }
+    }
+
+    #[test]
+    fn blood_pressure() {
+        for inp in ["120/80", "120 80", " 120 / 80 "] {
+            let bp = parse_blood_pressure(inp).unwrap();
+            assert_eq!((bp.systolic, bp.diastolic), (120, 80), "{inp}");
+        }
+        assert_eq!(
+            parse_blood_pressure("120/80").unwrap().to_string(),
+            "120/80"
+        );
+        for inp in ["", "120", "120/80/60", "120/abc"] {
+            assert!(
+                matches!(
+                    parse_blood_pressure(inp),
+                    Err(ParseError::BloodPressureFormat)
+                ),
+                "{inp}"
+            );
+        }
+    }
+
+    #[test]
+    fn teichholz() {
+        // (диаметр, объём): 7 / (2,4 + D) · D³
+        let cases = [(5.0, 118.243_243), (3.5, 50.868_644), (0.0, 0.0)];
+        for (diameter, volume) in cases {
+            assert_close(teichholz_volume(diameter), volume);
+        }
+    }
+
+    #[test]
+    fn pisa() {
+        // r 1,0 см, Va 40 см/с, V max 500 см/с: 2π · 1 · 40 / 500
+        assert_close(pisa_eroa(1.0, 40.0, 500.0).unwrap(), 0.502_654_8);
+        assert_eq!(
+            pisa_eroa(1.0, 40.0, 0.0),
+            Err(NotCalculated::Zero("пиковая скорость регургитации"))
+        );
+    }
+}
@@ -1,9 +1,23 @@
+use crate::grading::{
+    AortaInput, AorticProsthesisFunction, AorticProsthesisInput, AorticSize, AorticStenosis,
+    ArInput, AsGrade, AsInput, AvProsthesisFunction, DiastolicFunction, DiastolicInput,
+    MitralStenosis, MrInput, MsInput, PhInput, PulmonaryHypertension, PulmonaryStenosis,
+    RvFunction, RvInput, StrainFunction, WallMotion, WallMotionAnalysis, WallMotionScores,
+    estimate_right_atrium_pressure, grade_aortic_regurgitation, grade_mitral_regurgitation,
+};
 use crate::promptget::{
-    AutoValue, PreciseNum, RenderToString, ask_selection, calc_age, get_date, get_int, get_int_if,
-    get_num, get_num_if, get_num_opt, get_num_opt_if, get_num_x_num, get_string,
+    AutoValue, Beats, Derived, NotCalculated, PreciseNum, RenderToString, ask_selection, calc_age,
+    checked_div, finite, get_date, get_int, get_int_if, get_num, get_num_beats, get_num_beats_if,
+    get_num_beats_opt, get_num_beats_opt_if, get_num_if, get_num_opt, get_num_opt_if,
+    get_num_x_num, get_segment_values_opt, get_string, get_wall_motion, measured, pisa_eroa,
+    teichholz_volume, vti_volume,
+};
+use crate::reporttypes::{
+    AorticProsthesis, AvProsthesis, CalculatedReportData, ExtendedDiastology, RawReportData, Shunt,
+    Strain,
 };
-use crate::reporttypes::{CalculatedReportData, RawReportData};
 use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use strum_macros::EnumIter;
 
@@ -34,6 +48,66 @@ impl fmt::Display for Department {
     }
 }
 
+// формула площади поверхности тела: рост в см, вес в кг
+#[derive(Debug, Clone, Copy, EnumIter, Serialize, Deserialize)]
+pub enum BsaFormula {
+    DuBois,
+    Mosteller,
+    Haycock,
+    GehanGeorge,
+}
+
+impl BsaFormula {
+    pub fn text(self) -> &'static str {
+        match self {
+            BsaFormula::DuBois => "DuBois",
+            BsaFormula::Mosteller => "Mosteller",
+            BsaFormula::Haycock => "Haycock",
+            BsaFormula::GehanGeorge => "Gehan-George",
+        }
+    }
+
+    pub fn body_surface_area(self, height: f64, weight: f64) -> f64 {
+        match self {
+            BsaFormula::DuBois => 0.007184 * height.powf(0.725) * weight.powf(0.425),
+            BsaFormula::Mosteller => (height * weight / 3600.0).sqrt(),
+            BsaFormula::Haycock => 0.024265 * height.powf(0.3964) * weight.powf(0.5378),
+            BsaFormula::GehanGeorge => 0.0235 * height.powf(0.42246) * weight.powf(0.51456),
+        }
+    }
+}
+
+impl fmt::Display for BsaFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    pub fn text(self) -> &'static str {
+        match self {
+            Sex::Male => "мужской",
+            Sex::Female => "женский",
+        }
+    }
+
+    pub fn is_male(self) -> bool {
+        matches!(self, Sex::Male)
+    }
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
 #[derive(Debug, Clone, Copy, EnumIter)]
 pub enum ValveLeaflets {
     Normal,
@@ -112,6 +186,10 @@ impl YesNo {
         }
     }
 
+    pub fn is_yes(self) -> bool {
+        matches!(self, YesNo::Yes)
+    }
+
     pub fn render_to_string(&self, ctxt: MitVal) -> String {
         match self {
             Self::No => "".to_owned(),
@@ -181,6 +259,256 @@ impl RenderToString for PericardialEffusion {
     }
 }
 
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum RegurgitationDegree {
+    None,
+    Degree1,
+    Degree2,
+    Degree3,
+    Degree4,
+}
+
+impl RegurgitationDegree {
+    pub fn text(self) -> &'static str {
+        match self {
+            RegurgitationDegree::None => "нет",
+            RegurgitationDegree::Degree1 => "I степени (незначительная)",
+            RegurgitationDegree::Degree2 => "II степени (умеренная)",
+            RegurgitationDegree::Degree3 => "III степени (выраженная)",
+            RegurgitationDegree::Degree4 => "IV степени (массивная/торрентная)",
+        }
+    }
+
+    pub fn is_present(self) -> bool {
+        !matches!(self, RegurgitationDegree::None)
+    }
+
+    pub fn render_to_string(&self, valve: &str) -> String {
+        match self {
+            Self::None => "".to_owned(),
+            degree => format!("{} регургитация {}. ", valve, degree.text()),
+        }
+    }
+}
+
+impl fmt::Display for RegurgitationDegree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum FlowReversal {
+    Absent,
+    BriefEarlyDiastolic,
+    Holodiastolic,
+}
+
+impl FlowReversal {
+    pub fn text(self) -> &'static str {
+        match self {
+            FlowReversal::Absent => "нет",
+            FlowReversal::BriefEarlyDiastolic => "кратковременный раннедиастолический",
+            FlowReversal::Holodiastolic => "голодиастолический",
+        }
+    }
+
+    pub fn is_holodiastolic(self) -> bool {
+        matches!(self, FlowReversal::Holodiastolic)
+    }
+}
+
+impl fmt::Display for FlowReversal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum AorticValveMorphology {
+    Tricuspid,
+    Bicuspid,
+    Unicuspid,
+    Prosthetic,
+}
+
+impl AorticValveMorphology {
+    pub fn text(self) -> &'static str {
+        match self {
+            AorticValveMorphology::Tricuspid => "трехстворчатый",
+            AorticValveMorphology::Bicuspid => "двустворчатый",
+            AorticValveMorphology::Unicuspid => "одностворчатый",
+            AorticValveMorphology::Prosthetic => "протез",
+        }
+    }
+
+    pub fn is_prosthetic(self) -> bool {
+        matches!(self, AorticValveMorphology::Prosthetic)
+    }
+}
+
+impl fmt::Display for AorticValveMorphology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// классификация двустворчатого АК по Sievers
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum SieversType {
+    Type0Lateral,
+    Type0AnteroPosterior,
+    Type1LeftRight,
+    Type1RightNon,
+    Type1NonLeft,
+    Type2,
+}
+
+impl SieversType {
+    pub fn text(self) -> &'static str {
+        match self {
+            SieversType::Type0Lateral => "тип 0, латеральное расположение створок",
+            SieversType::Type0AnteroPosterior => "тип 0, передне-заднее расположение створок",
+            SieversType::Type1LeftRight => "тип 1, сращение L-R",
+            SieversType::Type1RightNon => "тип 1, сращение R-N",
+            SieversType::Type1NonLeft => "тип 1, сращение N-L",
+            SieversType::Type2 => "тип 2",
+        }
+    }
+}
+
+impl fmt::Display for SieversType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ProsthesisType {
+    Mechanical,
+    Biological,
+}
+
+impl ProsthesisType {
+    pub fn text(self) -> &'static str {
+        match self {
+            ProsthesisType::Mechanical => "механический",
+            ProsthesisType::Biological => "биологический",
+        }
+    }
+
+    pub fn is_mechanical(self) -> bool {
+        matches!(self, ProsthesisType::Mechanical)
+    }
+}
+
+impl fmt::Display for ProsthesisType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// МК и ТК: собственный клапан или протез
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ValveMorphology {
+    Native,
+    Prosthetic,
+}
+
+impl ValveMorphology {
+    pub fn text(self) -> &'static str {
+        match self {
+            ValveMorphology::Native => "нативный",
+            ValveMorphology::Prosthetic => "протез",
+        }
+    }
+
+    pub fn is_prosthetic(self) -> bool {
+        matches!(self, ValveMorphology::Prosthetic)
+    }
+}
+
+impl fmt::Display for ValveMorphology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ParavalvularLeak {
+    None,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl ParavalvularLeak {
+    pub fn text(self) -> &'static str {
+        match self {
+            ParavalvularLeak::None => "нет",
+            ParavalvularLeak::Mild => "незначительная",
+            ParavalvularLeak::Moderate => "умеренная",
+            ParavalvularLeak::Severe => "выраженная",
+        }
+    }
+
+    pub fn is_significant(self) -> bool {
+        matches!(self, ParavalvularLeak::Moderate | ParavalvularLeak::Severe)
+    }
+}
+
+impl fmt::Display for ParavalvularLeak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ShuntDefect {
+    Asd,
+    Vsd,
+    Pda,
+}
+
+impl ShuntDefect {
+    pub fn text(self) -> &'static str {
+        match self {
+            ShuntDefect::Asd => "ДМПП",
+            ShuntDefect::Vsd => "ДМЖП",
+            ShuntDefect::Pda => "ОАП",
+        }
+    }
+}
+
+impl fmt::Display for ShuntDefect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ShuntDirection {
+    LeftToRight,
+    RightToLeft,
+    Bidirectional,
+}
+
+impl ShuntDirection {
+    pub fn text(self) -> &'static str {
+        match self {
+            ShuntDirection::LeftToRight => "слева направо",
+            ShuntDirection::RightToLeft => "справа налево",
+            ShuntDirection::Bidirectional => "двунаправленный",
+        }
+    }
+}
+
+impl fmt::Display for ShuntDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum CardNumber {
     Ak(i64),
@@ -196,16 +524,98 @@ impl CardNumber {
     }
 }
 
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum Rhythm {
+    Sinus,
+    AtrialFibrillation,
+    Paced,
+    Other,
+}
+
+impl Rhythm {
+    pub fn text(self) -> &'static str {
+        match self {
+            Rhythm::Sinus => "синусовый",
+            Rhythm::AtrialFibrillation => "фибрилляция предсердий",
+            Rhythm::Paced => "навязанный (ЭКС)",
+            Rhythm::Other => "иной",
+        }
+    }
+
+    pub fn is_af(self) -> bool {
+        matches!(self, Rhythm::AtrialFibrillation)
+    }
+
+    // при ФП нет волны А: E/A, TDI (e'/a') и проба Вальсальвы не оцениваются
+    pub fn has_a_wave(self) -> bool {
+        !self.is_af()
+    }
+}
+
+impl fmt::Display for Rhythm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum IvcCollapse {
+    Over50,
+    Under50,
+}
+
+impl IvcCollapse {
+    pub fn text(self) -> &'static str {
+        match self {
+            IvcCollapse::Over50 => "более 50%",
+            IvcCollapse::Under50 => "менее 50%",
+        }
+    }
+
+    pub fn is_over_50(self) -> bool {
+        matches!(self, IvcCollapse::Over50)
+    }
+}
+
+impl fmt::Display for IvcCollapse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum MeanPapMethod {
+    PrGradient,
+    Chemla,
+    Mahan,
+}
+
+impl MeanPapMethod {
+    pub fn text(self) -> &'static str {
+        match self {
+            MeanPapMethod::PrGradient => "по градиенту ЛР и ДПП",
+            MeanPapMethod::Chemla => "по формуле Chemla",
+            MeanPapMethod::Mahan => "по формуле Mahan",
+        }
+    }
+}
+
+impl fmt::Display for MeanPapMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
 #[derive(Debug, Clone, Copy, EnumIter)]
 pub enum AtriumPressure {
-    Plus3,
+    ByIvc,
     Other,
 }
 
 impl fmt::Display for AtriumPressure {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Plus3 => write!(f, "3"),
+            Self::ByIvc => write!(f, "по НПВ"),
             Self::Other => write!(f, "иное"),
         }
     }
@@ -215,15 +625,121 @@ impl AtriumPressure {
     fn is_other(&self) -> bool {
         match self {
             Self::Other => true,
-            Self::Plus3 => false,
+            Self::ByIvc => false,
         }
     }
 }
 
-impl RawReportData {
+impl AorticProsthesis {
+    pub fn gather() -> Self {
+        let kind: ProsthesisType = ask_selection("Тип протеза");
+        let size = get_num("Размер протеза, мм", 0);
+        let eoa = get_num_opt("EOA протеза", 2);
+        let dvi = get_num_opt("DVI протеза", 2);
+        let acceleration_time = get_num_opt("AcT протеза, мс", 0);
+        Self {
+            kind,
+            size,
+            eoa,
+            dvi,
+            acceleration_time,
+        }
+    }
+}
+
+impl AvProsthesis {
+    // VTIprMV/VTIВТЛЖ спрашивается только для МК
+    pub fn gather(valve: &str, with_vti_ratio: bool) -> Self {
+        let kind: ProsthesisType = ask_selection(&format!("{}: тип протеза", valve));
+        let size = get_num(&format!("{}: размер протеза, мм", valve), 0);
+        let peak_e = get_num(&format!("{}: E на протезе, м/с", valve), 1);
+        let mid_grad = get_num(&format!("{}: средний градиент на протезе", valve), 1);
+        let presh_time = get_num_opt(&format!("{}: PHT на протезе", valve), 0);
+        let vti_ratio = get_num_opt_if(with_vti_ratio, "VTIprMV/VTIВТЛЖ", 1);
+        let paravalvular_leak: ParavalvularLeak =
+            ask_selection(&format!("{}: парапротезная фистула", valve));
+        Self {
+            kind,
+            size,
+            peak_e,
+            mid_grad,
+            presh_time,
+            vti_ratio,
+            paravalvular_leak,
+        }
+    }
+}
+
+impl Shunt {
+    // ВТЛЖ повторно не спрашивается, если уже введен для УО
+    pub fn gather(lvot_diameter: Option<PreciseNum>, lvot_vti: Option<PreciseNum>) -> Self {
+        let defect: ShuntDefect = ask_selection("Дефект");
+        let defect_size = get_num_opt("Размер дефекта", 1);
+        let direction: ShuntDirection = ask_selection("Направление сброса");
+        let rvot_diameter = get_num("Диаметр ВТПЖ", 1);
+        let rvot_vti = get_num("VTI ВТПЖ", 1);
+        let (lvot_diameter, lvot_vti) = match (lvot_diameter, lvot_vti) {
+            (Some(d), Some(vti)) => (d, vti),
+            _ => (get_num("Диаметр ВТЛЖ", 1), get_num("VTI ВТЛЖ", 1)),
+        };
+        Self {
+            defect,
+            defect_size,
+            direction,
+            rvot_diameter,
+            rvot_vti,
+            lvot_diameter,
+            lvot_vti,
+        }
+    }
+}
+
+impl ExtendedDiastology {
     pub fn gather() -> Self {
+        let deceleration_time = get_num_opt("DT", 0);
+        let isovolumic_relaxation_time = get_num_opt("IVRT", 0);
+        let pv_systolic = get_num_opt("ЛВ: S", 0);
+        let pv_diastolic = get_num_if(pv_systolic.is_some(), "ЛВ: D", 0);
+        let pv_atrial_reversal = get_num_opt("ЛВ: Ar", 0);
+        let ar_a_duration =
+            get_num_opt_if(pv_atrial_reversal.is_some(), "Длительность Ar − A, мс", 0);
+        let la_reservoir_strain = get_num_opt("Резервуарная деформация ЛП, %", 0);
+        Self {
+            deceleration_time,
+            isovolumic_relaxation_time,
+            pv_systolic,
+            pv_diastolic,
+            pv_atrial_reversal,
+            ar_a_duration,
+            la_reservoir_strain,
+        }
+    }
+}
+
+impl Strain {
+    // резервуарная деформация ЛП не спрашивается повторно, если введена в диастологии
+    pub fn gather(la_reservoir: Option<PreciseNum>) -> Self {
+        // GLS и деформация ПЖ отрицательные; введенное без минуса приводится к отрицательному
+        let negative = |v: PreciseNum| PreciseNum::from_float(-v.value().abs(), 1);
+        let gls = negative(get_num("GLS ЛЖ, %", 1));
+        let segments = get_segment_values_opt("Региональная деформация ЛЖ, %", 0);
+        let rv_free_wall = get_num_opt("Деформация свободной стенки ПЖ, %", 1).map(negative);
+        let la_reservoir = la_reservoir.or_else(|| get_num_opt("Резервуарная деформация ЛП, %", 0));
+        Self {
+            gls,
+            segments,
+            rv_free_wall,
+            la_reservoir,
+        }
+    }
+}
+
+impl RawReportData {
+    // формула ППТ берется из настроек, если не задана — спрашивается
+    pub fn gather(bsa_formula: Option<BsaFormula>) -> Self {
         let name = get_string("ФИО");
         let birthday = get_date("Дата рождения");
+        let sex: Sex = ask_selection("Пол");
         let department: Department = ask_selection("Отделение");
 
         let card_number: CardNumber = match department {
@@ -231,9 +747,15 @@ impl RawReportData {
             _ => CardNumber::Ib(get_int("ИБ№")),
         };
 
+        let rhythm: Rhythm = ask_selection("Ритм");
+
         let height = get_num("Рост", 0);
         let weight = get_num("Вес", 0);
-        let pulse = get_num("ЧСС", 0);
+        let bsa_formula: BsaFormula = match bsa_formula {
+            Some(f) => f,
+            None => ask_selection("Формула ППТ"),
+        };
+        let (pulse, pulse_beats) = get_num_beats("ЧСС", 0);
         let aortic_sinus_diameter = get_num("Ао", 1);
         let ascending_aorta_diameter = get_num("ВА", 1);
         let left_atrium = get_num("ЛП", 1);
@@ -244,82 +766,253 @@ impl RawReportData {
         let right_atrium_volume = get_num("ПП V", 0);
         let right_ventricle = get_num("ПЗР ПЖ", 1);
         let right_ventricle_baz = get_num("ПЖ баз", 1);
+        let left_ventricle_baz = get_num_opt("ЛЖ баз", 1);
         let right_ventricle_medium = get_num_opt("ПЖ ср", 1);
         let right_ventricle_wall_thickness = get_num_opt("ПСПЖ", 1);
-        let tapse = get_num_opt("TAPSE", 1);
-        let left_ventricle_diastolic_size = get_num("КДР", 1);
-        let left_ventricle_systolic_size = get_num("КСР", 1);
+        let (tapse, tapse_beats) = get_num_beats_opt("TAPSE", 1);
+        let right_ventricle_diastolic_area = get_num_opt("S ПЖ диаст., см²", 1);
+        let right_ventricle_systolic_area = get_num_if(
+            right_ventricle_diastolic_area.is_some(),
+            "S ПЖ сист., см²",
+            1,
+        );
+        let tricuspid_s_prime = get_num_opt("S' ТК (TDI), см/с", 1);
+        let right_ventricle_rimp = get_num_opt("RIMP ПЖ (TDI)", 2);
+        let (left_ventricle_diastolic_size, left_ventricle_diastolic_size_beats) =
+            get_num_beats("КДР", 1);
+        let (left_ventricle_systolic_size, left_ventricle_systolic_size_beats) =
+            get_num_beats("КСР", 1);
         let septum_thickness = get_num("МЖП", 1);
         let septum_thickness_baz = get_num_opt("МЖП баз", 1);
         let posterior_wall_thickness = get_num("ЗС", 1);
-        let simpson_end_diastolic_volume = get_num("КДО (по Симпсону)", 0);
-        let simpson_end_systolic_volume = get_num("КСО (по Симпсону)", 0);
+        let (simpson_end_diastolic_volume, simpson_end_diastolic_volume_beats) =
+            get_num_beats("КДО (по Симпсону)", 0);
+        let (simpson_end_systolic_volume, simpson_end_systolic_volume_beats) =
+            get_num_beats("КСО (по Симпсону)", 0);
+        let has_wall_motion: YesNo = ask_selection("Локальная сократимость ЛЖ (17 сегментов)");
+        let wall_motion: Option<WallMotionScores> = if has_wall_motion.is_yes() {
+            Some(get_wall_motion("Баллы сократимости").map(|s| s.and_then(WallMotion::from_score)))
+        } else {
+            None
+        };
 
-        let stroke_volume = get_num_opt("УО", 0);
+        // УО по допплеру: площадь ВТЛЖ × VTI ВТЛЖ, без них — по Симпсону
+        let lvot_diameter = get_num_opt("Диаметр ВТЛЖ", 1);
+        let (lvot_vti, lvot_vti_beats) = get_num_beats_if(lvot_diameter.is_some(), "VTI ВТЛЖ", 1);
 
-        let shutters_aortal: ValveLeaflets = ask_selection("АК");
+        let aortic_valve_morphology: AorticValveMorphology = ask_selection("Морфология АК");
+        let bicuspid_type: Option<SieversType> = match aortic_valve_morphology {
+            AorticValveMorphology::Bicuspid => Some(ask_selection("Тип по Sievers")),
+            _ => None,
+        };
+        let aortic_prosthesis: Option<AorticProsthesis> = if aortic_valve_morphology.is_prosthetic()
+        {
+            Some(AorticProsthesis::gather())
+        } else {
+            None
+        };
+        // створки механического протеза не описываются
+        let shutters_aortal: Option<ValveLeaflets> = match aortic_prosthesis {
+            Some(p) if p.kind.is_mechanical() => None,
+            _ => Some(ask_selection("АК")),
+        };
         let opening_amplitude = get_num("Амплитуда раскрытия", 1);
-        let max_velocity_aortal = get_num("Макс скорость", 1);
+        let (max_velocity_aortal, max_velocity_aortal_beats) = get_num_beats("Макс скорость", 1);
         let max_grad_aortal = get_num("Макс градиент", 0);
-        let stenosis: Stenosis = ask_selection("Стеноз");
-        let mid_grad = get_num_if(stenosis.is_yes(), "Средний градиент", 0);
-        let s_doppler = get_num_if(stenosis.is_yes(), "Площадь по допплеру", 1);
+        // при протезе стеноз оценивается по нормам протеза
+        let stenosis: Stenosis = if aortic_prosthesis.is_some() {
+            Stenosis::No
+        } else {
+            ask_selection("Стеноз")
+        };
+        let mid_grad = get_num_if(
+            stenosis.is_yes() || aortic_prosthesis.is_some(),
+            "Средний градиент",
+            0,
+        );
+        let aortic_vti = get_num_if(stenosis.is_yes() && lvot_diameter.is_some(), "VTI АК", 1);
         let s_planim = get_num_if(stenosis.is_yes(), "Площадь планиметрически", 1);
-        let presh_time = get_num_opt("PHT", 0);
-        let vena_contracta = get_num_if(presh_time.is_some(), "VC АР", 1);
+        let aortic_regurgitation: YesNo = ask_selection("Аортальная регургитация");
+        let presh_time = get_num_opt_if(aortic_regurgitation.is_yes(), "PHT АР", 0);
+        let vena_contracta = get_num_opt_if(aortic_regurgitation.is_yes(), "VC АР", 1);
+        let ar_jet_width_ratio = get_num_opt_if(
+            aortic_regurgitation.is_yes(),
+            "Ширина струи АР / ВТЛЖ, %",
+            0,
+        );
+        let aortic_flow_reversal: Option<FlowReversal> = if aortic_regurgitation.is_yes() {
+            Some(ask_selection(
+                "Ретроградный диастолический кровоток в нисходящей аорте",
+            ))
+        } else {
+            None
+        };
+        let ar_pisa_radius = get_num_opt_if(aortic_regurgitation.is_yes(), "Радиус PISA АР", 2);
+        let ar_aliasing_velocity =
+            get_num_if(ar_pisa_radius.is_some(), "Скорость алиасинга PISA АР", 0);
+        let ar_max_velocity = get_num_if(ar_pisa_radius.is_some(), "V max АР", 1);
+        let ar_vti = get_num_if(ar_pisa_radius.is_some(), "VTI АР", 0);
         let max_velocity_vt =
             get_num_opt_if(septum_thickness_baz.is_some(), "ВТЛЖ Макс скорость", 1);
         let max_grad_vt = get_num_if(max_velocity_vt.is_some(), "ВТЛЖ макс градиент", 0);
-        let shutters_mitral: ValveLeaflets = ask_selection("МК");
-        let calts_back_sash: YesNo = ask_selection("Кальцинат в основании задней створки");
-        let posterior_leaflet_base_calcification: YesNo =
-            ask_selection("Кальциноз основания задней створки, фиброзного кольца");
-        let peak_e = get_num("МК: Е", 0);
-        let peak_a = get_num("А", 0);
-        let tdi_vel: TdiRelation = ask_selection("TDI");
-        let e_sept = get_num("E sept", 0);
-        let e_lat = get_num("E’ lat", 0);
-        let max_velocity_mitral_valve = get_num_opt("МК Макс скорость", 1);
+        let mitral_valve_morphology: ValveMorphology = ask_selection("МК: нативный/протез");
+        let mitral_prosthesis: Option<AvProsthesis> = if mitral_valve_morphology.is_prosthetic() {
+            Some(AvProsthesis::gather("МК", true))
+        } else {
+            None
+        };
+        let mitral_native = mitral_prosthesis.is_none();
+        let shutters_mitral: Option<ValveLeaflets> = match mitral_prosthesis {
+            Some(p) if p.kind.is_mechanical() => None,
+            _ => Some(ask_selection("МК")),
+        };
+        // при протезе МК вопросы о нативном клапане и трансмитральном потоке не задаются
+        let (calts_back_sash, posterior_leaflet_base_calcification): (YesNo, YesNo) =
+            if mitral_native {
+                (
+                    ask_selection("Кальцинат в основании задней створки"),
+                    ask_selection("Кальциноз основания задней створки, фиброзного кольца"),
+                )
+            } else {
+                (YesNo::No, YesNo::No)
+            };
+        let with_a_wave = mitral_native && rhythm.has_a_wave();
+        let (peak_e, peak_e_beats) = get_num_beats_if(mitral_native, "МК: Е", 0);
+        let (peak_a, peak_a_beats) = get_num_beats_if(with_a_wave, "А", 0);
+        let tdi_vel: Option<TdiRelation> = if with_a_wave {
+            Some(ask_selection("TDI"))
+        } else {
+            None
+        };
+        let (e_sept, e_sept_beats) = get_num_beats_if(mitral_native, "E sept", 0);
+        let (e_lat, e_lat_beats) = get_num_beats_if(mitral_native, "E’ lat", 0);
+        let valsalva_e_a_change = get_num_opt_if(with_a_wave, "Δ E/A при пробе Вальсальвы", 1);
+        let myocardial_disease: YesNo = if mitral_native {
+            ask_selection("Поражение миокарда при сохранной ФВ")
+        } else {
+            YesNo::No
+        };
+        let has_extended_diastology: YesNo = if mitral_native {
+            ask_selection("Дополнительная диастолическая функция")
+        } else {
+            YesNo::No
+        };
+        let extended_diastology: Option<ExtendedDiastology> = if has_extended_diastology.is_yes() {
+            Some(ExtendedDiastology::gather())
+        } else {
+            None
+        };
+        let has_strain: YesNo = ask_selection("Деформация миокарда (speckle tracking)");
+        let strain: Option<Strain> = if has_strain.is_yes() {
+            Some(Strain::gather(
+                extended_diastology.and_then(|d| d.la_reservoir_strain),
+            ))
+        } else {
+            None
+        };
+        let (max_velocity_mitral_valve, max_velocity_mitral_valve_beats) =
+            get_num_beats_opt_if(mitral_native, "МК Макс скорость", 1);
         let max_grad_mitral_valve =
             get_num_if(max_velocity_mitral_valve.is_some(), "МК Макс градиент", 1);
-        let mid_grad_mitral_valve = get_num_if(
+        let (mid_grad_mitral_valve, mid_grad_mitral_valve_beats) = get_num_beats_if(
             max_velocity_mitral_valve.is_some(),
             "МК Средний градиент",
             1,
         );
-        let max_velocity_tricuspidal_regurgitation = get_num("ТК Макс скорость ТР", 1);
+        let (mitral_presh_time, mitral_presh_time_beats) =
+            get_num_beats_opt_if(max_velocity_mitral_valve.is_some(), "МК PHT", 0);
+        let mitral_s_planim = get_num_opt_if(
+            max_velocity_mitral_valve.is_some(),
+            "МК площадь планиметрически",
+            1,
+        );
+        let mitral_regurgitation: YesNo = ask_selection("Митральная регургитация");
+        let mr_vena_contracta = get_num_opt_if(mitral_regurgitation.is_yes(), "VC МР", 1);
+        let mr_pisa_radius = get_num_opt_if(mitral_regurgitation.is_yes(), "Радиус PISA МР", 2);
+        let mr_aliasing_velocity =
+            get_num_if(mr_pisa_radius.is_some(), "Скорость алиасинга PISA МР", 0);
+        let mr_max_velocity = get_num_if(mr_pisa_radius.is_some(), "V max МР", 1);
+        let mr_vti = get_num_if(mr_pisa_radius.is_some(), "VTI МР", 0);
+        let tricuspid_valve_morphology: ValveMorphology = ask_selection("ТК: нативный/протез");
+        let tricuspid_prosthesis: Option<AvProsthesis> =
+            if tricuspid_valve_morphology.is_prosthetic() {
+                Some(AvProsthesis::gather("ТК", false))
+            } else {
+                None
+            };
+        let shutters_tricuspid: Option<ValveLeaflets> = match tricuspid_prosthesis {
+            Some(p) if p.kind.is_mechanical() => None,
+            _ => Some(ask_selection("ТК")),
+        };
+        let tricuspid_regurgitation: RegurgitationDegree =
+            ask_selection("Трикуспидальная регургитация");
+        let tricuspid_annulus =
+            get_num_opt_if(tricuspid_prosthesis.is_none(), "Фиброзное кольцо ТК", 1);
+        let tr_vena_contracta = get_num_opt_if(tricuspid_regurgitation.is_present(), "VC ТР", 1);
+        let (max_velocity_tricuspidal_regurgitation, max_velocity_tricuspidal_regurgitation_beats) =
+            get_num_beats("ТК Макс скорость ТР", 1);
         let max_grad_tricuspidal_regurgitation = get_num("ТК макс градиент ТР", 0);
 
+        let (vena, vena_beats) = get_num_beats("НПВ", 1);
+        let ivc_collapse: IvcCollapse = ask_selection("Коллабирование НПВ на вдохе");
+
+        // по умолчанию ДПП оценивается по НПВ, при необходимости вводится вручную
         let right_atrium_pressure_choice: AtriumPressure = ask_selection(&format!(
-            "СДЛА: к {} прибавить",
-            max_grad_tricuspidal_regurgitation
+            "СДЛА: к {} прибавить ДПП (по НПВ — {} мм рт.ст.)",
+            max_grad_tricuspidal_regurgitation,
+            estimate_right_atrium_pressure(vena.value(), ivc_collapse.is_over_50())
         ));
         let right_atrium_pressure = get_int_if(right_atrium_pressure_choice.is_other(), "Иное");
 
+        let shutters_pulmonary: ValveLeaflets = ask_selection("ЛК");
+        let pulmonary_regurgitation: RegurgitationDegree =
+            ask_selection("Пульмональная регургитация");
         let pulmonary_artery = get_num("Диаметр ЛА", 1);
         let pulmonary_artery_right_branch = get_num_opt("Правая ветвь ЛА", 1);
         let pulmonary_artery_left_branch =
             get_num_if(pulmonary_artery_right_branch.is_some(), "Левая ветвь ЛА", 1);
-        let max_velocity_in_pulmonary_artery = get_num("ЛА макс. скорость", 1);
+        let (max_velocity_in_pulmonary_artery, max_velocity_in_pulmonary_artery_beats) =
+            get_num_beats("ЛА макс. скорость", 1);
         let max_grad_in_pulmonary_artery = get_num("ЛА макс градиент", 0);
-        let pulmonary_regurgitation_max_velocity = get_num_opt("ЛР макс. скорость", 1);
+        let (pulmonary_regurgitation_max_velocity, pulmonary_regurgitation_max_velocity_beats) =
+            get_num_beats_opt_if(pulmonary_regurgitation.is_present(), "ЛР макс. скорость", 1);
         let pulmonary_regurgitation_max_grad = get_num_if(
             pulmonary_regurgitation_max_velocity.is_some(),
             "ЛР макс градиент",
             0,
         );
-        let vena = get_num("НПВ", 1);
+        let (pulmonary_regurgitation_end_velocity, pulmonary_regurgitation_end_velocity_beats) =
+            get_num_beats_opt_if(
+                pulmonary_regurgitation.is_present(),
+                "ЛР конечнодиастолическая скорость",
+                1,
+            );
+        let pulmonary_acceleration_time = get_num_opt("AcT ЛА, мс", 0);
+        let mean_pap_method: MeanPapMethod = ask_selection("Метод расчета Ср.ДЛА");
+        let has_shunt: YesNo = ask_selection("Внутрисердечный шунт (Qp/Qs)");
+        let shunt: Option<Shunt> = if has_shunt.is_yes() {
+            Some(Shunt::gather(lvot_diameter, lvot_vti))
+        } else {
+            None
+        };
+        let septum_flattening: YesNo =
+            ask_selection("Уплощение МЖП (индекс эксцентриситета ЛЖ > 1,1)");
+        let rvot_notching: YesNo = ask_selection("Среднесистолическая выемка на потоке в ВТПЖ");
         let effusion: PericardialEffusion = ask_selection("Перикардиальный выпот");
 
         Self {
             name,
             birthday,
+            sex,
             department,
+            rhythm,
             card_number,
 
             height,
             weight,
+            bsa_formula,
             pulse,
+            pulse_beats,
 
             aortic_sinus_diameter,
             ascending_aorta_diameter,
@@ -334,67 +1027,164 @@ impl RawReportData {
 
             right_ventricle,
             right_ventricle_baz,
+            left_ventricle_baz,
             right_ventricle_medium,
             right_ventricle_wall_thickness,
             tapse,
+            tapse_beats,
+            right_ventricle_diastolic_area,
+            right_ventricle_systolic_area,
+            tricuspid_s_prime,
+            right_ventricle_rimp,
 
             left_ventricle_diastolic_size,
+            left_ventricle_diastolic_size_beats,
             left_ventricle_systolic_size,
+            left_ventricle_systolic_size_beats,
             septum_thickness,
             septum_thickness_baz,
             posterior_wall_thickness,
 
             simpson_end_diastolic_volume,
+            simpson_end_diastolic_volume_beats,
             simpson_end_systolic_volume,
-            stroke_volume,
+            simpson_end_systolic_volume_beats,
+            wall_motion,
 
+            aortic_valve_morphology,
+            bicuspid_type,
+            aortic_prosthesis,
             shutters_aortal,
             opening_amplitude,
             max_velocity_aortal,
+            max_velocity_aortal_beats,
             max_grad_aortal,
 
             mid_grad,
-            s_doppler,
+            lvot_diameter,
+            lvot_vti,
+            lvot_vti_beats,
+            aortic_vti,
             s_planim,
 
+            aortic_regurgitation,
             presh_time,
             vena_contracta,
+            ar_jet_width_ratio,
+            aortic_flow_reversal,
+            ar_pisa_radius,
+            ar_aliasing_velocity,
+            ar_max_velocity,
+            ar_vti,
 
             max_velocity_vt,
             max_grad_vt,
 
+            mitral_prosthesis,
             shutters_mitral,
             calts_back_sash,
             posterior_leaflet_base_calcification,
 
             peak_e,
+            peak_e_beats,
             peak_a,
+            peak_a_beats,
             tdi_vel,
             e_sept,
+            e_sept_beats,
             e_lat,
+            e_lat_beats,
+            valsalva_e_a_change,
+            extended_diastology,
+            strain,
+            myocardial_disease,
 
             max_velocity_mitral_valve,
+            max_velocity_mitral_valve_beats,
             max_grad_mitral_valve,
             mid_grad_mitral_valve,
+            mid_grad_mitral_valve_beats,
+            mitral_presh_time,
+            mitral_presh_time_beats,
+            mitral_s_planim,
+
+            mitral_regurgitation,
+            mr_vena_contracta,
+            mr_pisa_radius,
+            mr_aliasing_velocity,
+            mr_max_velocity,
+            mr_vti,
+
+            tricuspid_prosthesis,
+            shutters_tricuspid,
+            tricuspid_regurgitation,
+            tricuspid_annulus,
+            tr_vena_contracta,
 
             max_velocity_tricuspidal_regurgitation,
+            max_velocity_tricuspidal_regurgitation_beats,
             max_grad_tricuspidal_regurgitation,
             right_atrium_pressure,
 
+            shutters_pulmonary,
+            pulmonary_regurgitation,
+
             pulmonary_artery,
             pulmonary_artery_right_branch,
             pulmonary_artery_left_branch,
 
             max_velocity_in_pulmonary_artery,
+            max_velocity_in_pulmonary_artery_beats,
             max_grad_in_pulmonary_artery,
 
             pulmonary_regurgitation_max_velocity,
+            pulmonary_regurgitation_max_velocity_beats,
             pulmonary_regurgitation_max_grad,
+            pulmonary_regurgitation_end_velocity,
+            pulmonary_regurgitation_end_velocity_beats,
+            pulmonary_acceleration_time,
+            mean_pap_method,
+            shunt,
+
+            septum_flattening,
+            rvot_notching,
 
             vena,
+            vena_beats,
+            ivc_collapse,
             effusion,
         }
     }
+
+    // измерения, введённые по нескольким циклам, в порядке опроса
+    pub fn beats(&self) -> Vec<Beats> {
+        [
+            &self.pulse_beats,
+            &self.tapse_beats,
+            &self.left_ventricle_diastolic_size_beats,
+            &self.left_ventricle_systolic_size_beats,
+            &self.simpson_end_diastolic_volume_beats,
+            &self.simpson_end_systolic_volume_beats,
+            &self.lvot_vti_beats,
+            &self.max_velocity_aortal_beats,
+            &self.peak_e_beats,
+            &self.peak_a_beats,
+            &self.e_sept_beats,
+            &self.e_lat_beats,
+            &self.max_velocity_mitral_valve_beats,
+            &self.mid_grad_mitral_valve_beats,
+            &self.mitral_presh_time_beats,
+            &self.max_velocity_tricuspidal_regurgitation_beats,
+            &self.vena_beats,
+            &self.max_velocity_in_pulmonary_artery_beats,
+            &self.pulmonary_regurgitation_max_velocity_beats,
+            &self.pulmonary_regurgitation_end_velocity_beats,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
 }
 
 impl CalculatedReportData {
@@ -404,64 +1194,295 @@ impl CalculatedReportData {
         let height_v = raw.height.value();
         let weight_v = raw.weight.value();
 
-        let body_surface_area: f64 =
-            f64::powf(height_v as f64, 0.725) * f64::powf(weight_v as f64, 0.425) * 0.007;
+        // нулевой рост или вес дают нулевую ППТ — индексы не рассчитываются
+        let body_surface_area: Derived =
+            match finite(raw.bsa_formula.body_surface_area(height_v, weight_v)) {
+                Ok(bsa) if bsa <= 0.0 => Err(NotCalculated::Zero("ППТ")),
+                bsa => bsa,
+            };
+        let per_bsa = |v: f64| body_surface_area.and_then(|bsa| checked_div(v, bsa, "ППТ"));
 
-        let left_atrium_index: f64 = raw.left_atrium_volume.value() / body_surface_area;
+        let left_atrium_index: Derived = per_bsa(raw.left_atrium_volume.value());
 
         let age: i32 = calc_age(raw.birthday, today);
 
         let edv = raw.simpson_end_diastolic_volume.value();
         let esv = raw.simpson_end_systolic_volume.value();
 
-        let ejection_fraction: f64 = (edv - esv) / edv * 100.0;
+        let ejection_fraction: Derived = checked_div(edv - esv, edv, "КДО").map(|v| v * 100.0);
+
+        let aortic_size: Result<AorticSize, NotCalculated> = body_surface_area.map(|bsa| {
+            AorticSize::assess(&AortaInput {
+                sinus_diameter: raw.aortic_sinus_diameter.value(),
+                ascending_diameter: raw.ascending_aorta_diameter.value(),
+                age: age as f64,
+                body_surface_area: bsa,
+                height: height_v / 100.0,
+                male: raw.sex.is_male(),
+            })
+        });
 
         let lvidd = raw.left_ventricle_diastolic_size.value();
         let ivs = raw.septum_thickness.value();
         let pw = raw.posterior_wall_thickness.value();
 
-        let left_ventricle_mass: f64 =
-            0.8 * (1.04 * ((lvidd + ivs + pw).powi(3) - lvidd.powi(3))) + 0.6;
-
-        let left_ventricle_mass_index: f64 = left_ventricle_mass / body_surface_area;
-
-        let relative_wall_thickness: f64 = 2.0 * pw / lvidd;
-
-        // если вручную то по допплеру, иначе авто по Симпсону
-        let stroke_volume = match raw.stroke_volume {
-            Some(v) => AutoValue {
-                value: v,
+        let left_ventricle_mass: Derived =
+            finite(0.8 * (1.04 * ((lvidd + ivs + pw).powi(3) - lvidd.powi(3))) + 0.6);
+
+        let left_ventricle_mass_index: Derived = left_ventricle_mass.and_then(per_bsa);
+
+        let relative_wall_thickness: Derived = checked_div(2.0 * pw, lvidd, "КДР");
+
+        // М-режим: фракция укорочения и объёмы по Тейхольцу
+        let lvids = raw.left_ventricle_systolic_size.value();
+        let fractional_shortening: Derived =
+            checked_div(lvidd - lvids, lvidd, "КДР").map(|v| v * 100.0);
+        let teichholz_end_diastolic_volume: Derived = finite(teichholz_volume(lvidd));
+        let teichholz_end_systolic_volume: Derived = finite(teichholz_volume(lvids));
+        let teichholz_ejection_fraction: Derived = teichholz_end_diastolic_volume.and_then(|edv| {
+            teichholz_end_systolic_volume
+                .and_then(|esv| checked_div(edv - esv, edv, "КДО по Тейхольцу"))
+                .map(|v| v * 100.0)
+        });
+
+        // УО по допплеру, иначе авто по Симпсону
+        let stroke_volume = match (raw.lvot_diameter, raw.lvot_vti) {
+            (Some(d), Some(vti)) => AutoValue {
+                value: finite(vti_volume(d.value(), vti.value())),
                 auto: false,
             },
-            None => AutoValue {
-                value: PreciseNum::from_float(edv - esv, 0),
+            _ => AutoValue {
+                value: finite(edv - esv),
                 auto: true,
             },
         };
-        let sv = stroke_volume.value.value();
+        let stroke_volume_index: Derived = stroke_volume.value.and_then(per_bsa);
+
+        // Qp/Qs: при ОАП легочный кровоток измеряется в ВТЛЖ, системный — в ВТПЖ
+        let qp_qs: Derived = match raw.shunt {
+            Some(s) => {
+                let rvot = vti_volume(s.rvot_diameter.value(), s.rvot_vti.value());
+                let lvot = vti_volume(s.lvot_diameter.value(), s.lvot_vti.value());
+                match s.defect {
+                    ShuntDefect::Pda => checked_div(lvot, rvot, "кровоток в ВТПЖ"),
+                    _ => checked_div(rvot, lvot, "кровоток в ВТЛЖ"),
+                }
+            }
+            None => Err(NotCalculated::Missing),
+        };
 
-        let cardiac_output: f64 = raw.pulse.value() * sv / 1000.0;
-        let cardiac_index: f64 = cardiac_output / body_surface_area;
+        // площадь отверстия АК по уравнению непрерывности
+        let (s_doppler, dimensionless_index) =
+            match (raw.lvot_diameter, raw.lvot_vti, raw.aortic_vti) {
+                (Some(d), Some(lvot_vti), Some(av_vti)) => (
+                    checked_div(
+                        vti_volume(d.value(), lvot_vti.value()),
+                        av_vti.value(),
+                        "VTI АК",
+                    ),
+                    checked_div(lvot_vti.value(), av_vti.value(), "VTI АК"),
+                ),
+                _ => (Err(NotCalculated::Missing), Err(NotCalculated::Missing)),
+            };
+        let aortic_valve_area_index: Derived = s_doppler.and_then(per_bsa);
 
-        let peak_e_div_peak_a: f64 = raw.peak_e.value() / raw.peak_a.value();
+        // протез оценивается по собственным нормам, а не как нативный клапан
+        let aortic_stenosis = match raw.aortic_prosthesis {
+            Some(_) => AorticStenosis {
+                grade: AsGrade::None,
+                low_gradient: None,
+            },
+            None => AorticStenosis::assess(&AsInput {
+                max_velocity: raw.max_velocity_aortal.value(),
+                mid_grad: raw.mid_grad.map(|v| v.value()),
+                valve_area: s_doppler.ok().or(raw.s_planim.map(|v| v.value())),
+                leaflets_changed: !matches!(raw.shutters_aortal, Some(ValveLeaflets::Normal)),
+                ejection_fraction: ejection_fraction.ok(),
+                stroke_volume_index: stroke_volume_index.ok(),
+            }),
+        };
+        let aortic_prosthesis_eoa_index: Derived =
+            measured(raw.aortic_prosthesis.and_then(|p| p.eoa)).and_then(per_bsa);
+        let aortic_prosthesis_function = raw.aortic_prosthesis.map(|p| {
+            AorticProsthesisFunction::assess(&AorticProsthesisInput {
+                mechanical: p.kind.is_mechanical(),
+                size: p.size.value(),
+                max_velocity: raw.max_velocity_aortal.value(),
+                mid_grad: raw.mid_grad.map(|v| v.value()),
+                dvi: p.dvi.map(|v| v.value()),
+                eoa: p.eoa.map(|v| v.value()),
+                eoa_index: aortic_prosthesis_eoa_index.ok(),
+                acceleration_time: p.acceleration_time.map(|v| v.value()),
+            })
+        });
+
+        // EROA и объём регургитации АР по PISA
+        let ar_eroa: Derived = match (
+            raw.ar_pisa_radius,
+            raw.ar_aliasing_velocity,
+            raw.ar_max_velocity,
+        ) {
+            (Some(r), Some(va), Some(v)) => pisa_eroa(r.value(), va.value(), v.value() * 100.0),
+            _ => Err(NotCalculated::Missing),
+        };
+        let ar_regurgitant_volume: Derived =
+            ar_eroa.and_then(|eroa| measured(raw.ar_vti).map(|vti| eroa * vti));
+
+        let aortic_regurgitation_grade = if raw.aortic_regurgitation.is_yes() {
+            grade_aortic_regurgitation(&ArInput {
+                presh_time: raw.presh_time.map(|v| v.value()),
+                vena_contracta: raw.vena_contracta.map(|v| v.value()),
+                jet_width_ratio: raw.ar_jet_width_ratio.map(|v| v.value()),
+                holodiastolic_reversal: raw.aortic_flow_reversal.map(|v| v.is_holodiastolic()),
+                eroa: ar_eroa.ok(),
+                regurgitant_volume: ar_regurgitant_volume.ok(),
+            })
+        } else {
+            None
+        };
 
-        let e_div_e_aps: f64 =
-            raw.peak_e.value() / ((raw.e_sept.value() + raw.e_lat.value()) / 2.0);
+        // EROA и объём регургитации МР по PISA
+        let mr_eroa: Derived = match (
+            raw.mr_pisa_radius,
+            raw.mr_aliasing_velocity,
+            raw.mr_max_velocity,
+        ) {
+            (Some(r), Some(va), Some(v)) => pisa_eroa(r.value(), va.value(), v.value() * 100.0),
+            _ => Err(NotCalculated::Missing),
+        };
+        let mr_regurgitant_volume: Derived =
+            mr_eroa.and_then(|eroa| measured(raw.mr_vti).map(|vti| eroa * vti));
+
+        let mitral_regurgitation_grade = if raw.mitral_regurgitation.is_yes() {
+            grade_mitral_regurgitation(&MrInput {
+                vena_contracta: raw.mr_vena_contracta.map(|v| v.value()),
+                eroa: mr_eroa.ok(),
+                regurgitant_volume: mr_regurgitant_volume.ok(),
+            })
+        } else {
+            None
+        };
 
-        // В старом коде это было right_atrium_pressure.
-        // Сейчас у тебя хранится "что прибавить" как Option<i64>.
-        let right_atrium_pressure = raw.right_atrium_pressure.unwrap_or(3);
-        let rap = right_atrium_pressure as f64;
+        let cardiac_output: Derived = stroke_volume
+            .value
+            .and_then(|sv| finite(raw.pulse.value() * sv / 1000.0));
+        let cardiac_index: Derived = cardiac_output.and_then(per_bsa);
+
+        let peak_e_div_peak_a: Derived = match (raw.peak_e, raw.peak_a) {
+            (Some(e), Some(a)) => checked_div(e.value(), a.value(), "Peak A"),
+            _ => Err(NotCalculated::Missing),
+        };
+
+        let e_div_e_aps: Derived = match (raw.peak_e, raw.e_sept, raw.e_lat) {
+            (Some(e), Some(s), Some(l)) => checked_div(
+                e.value(),
+                (s.value() + l.value()) / 2.0,
+                "сумма E’ sept и E’ lat",
+            ),
+            _ => Err(NotCalculated::Missing),
+        };
+
+        // при протезе МК диастолическая функция по трансмитральному потоку не оценивается
+        let diastolic_function = match raw.mitral_prosthesis {
+            Some(_) => None,
+            None => Some(DiastolicFunction::assess(&DiastolicInput {
+                peak_e: raw.peak_e.map(|v| v.value()),
+                peak_a: raw.peak_a.map(|v| v.value()),
+                e_sept: raw.e_sept.map(|v| v.value()),
+                e_lat: raw.e_lat.map(|v| v.value()),
+                left_atrium_index: left_atrium_index.ok(),
+                tr_velocity: Some(raw.max_velocity_tricuspidal_regurgitation.value()),
+                deceleration_time: raw
+                    .extended_diastology
+                    .and_then(|d| d.deceleration_time)
+                    .map(|v| v.value()),
+                valsalva_e_a_change: raw.valsalva_e_a_change.map(|v| v.value()),
+                pv_s_d_ratio: raw.extended_diastology.and_then(|d| d.pv_s_d_ratio()),
+                ar_a_duration: raw
+                    .extended_diastology
+                    .and_then(|d| d.ar_a_duration)
+                    .map(|v| v.value()),
+                ejection_fraction: ejection_fraction.ok(),
+                myocardial_disease: raw.myocardial_disease.is_yes(),
+            })),
+        };
 
-        let pulmonary_artery_systolic_pressure: f64 =
-            raw.max_grad_tricuspidal_regurgitation.value() + rap;
+        let mitral_prosthesis_function = raw
+            .mitral_prosthesis
+            .map(|p| AvProsthesisFunction::assess_mitral(&p.as_input()));
+        let tricuspid_prosthesis_function = raw
+            .tricuspid_prosthesis
+            .map(|p| AvProsthesisFunction::assess_tricuspid(&p.as_input()));
+
+        // ДПП: введенное вручную значение или оценка по НПВ
+        let right_atrium_pressure = raw.right_atrium_pressure.unwrap_or_else(|| {
+            estimate_right_atrium_pressure(raw.vena.value(), raw.ivc_collapse.is_over_50())
+        });
+        let rap = right_atrium_pressure as f64;
 
-        let pulmonary_artery_med_pressure: Option<PreciseNum> =
-            match raw.pulmonary_regurgitation_max_grad {
-                Some(v) => Some(PreciseNum::from_float(v.value() + rap, 0)),
-                None => None,
+        let pulmonary_artery_systolic_pressure: Derived =
+            finite(raw.max_grad_tricuspidal_regurgitation.value() + rap);
+        // нерассчитанное СДЛА в критериях не учитывается: 0 ниже всех порогов
+        let systolic_pressure = pulmonary_artery_systolic_pressure.unwrap_or(0.0);
+
+        let wall_motion_analysis = raw.wall_motion.map(|s| WallMotionAnalysis::assess(&s));
+
+        let strain_function = raw.strain.map(|s| StrainFunction::assess(&s.as_input()));
+
+        let right_ventricle_function = RvFunction::assess(&RvInput {
+            end_diastolic_area: raw.right_ventricle_diastolic_area.map(|v| v.value()),
+            end_systolic_area: raw.right_ventricle_systolic_area.map(|v| v.value()),
+            s_prime: raw.tricuspid_s_prime.map(|v| v.value()),
+            rimp: raw.right_ventricle_rimp.map(|v| v.value()),
+            tapse: raw.tapse.map(|v| v.value()),
+            systolic_pressure,
+        });
+
+        let pulmonary_hypertension = PulmonaryHypertension::assess(&PhInput {
+            tr_velocity: raw.max_velocity_tricuspidal_regurgitation.value(),
+            right_ventricle_baz: raw.right_ventricle_baz.value(),
+            left_ventricle_baz: raw.left_ventricle_baz.map(|v| v.value()),
+            septum_flattening: raw.septum_flattening.is_yes(),
+            tapse: raw.tapse.map(|v| v.value()),
+            systolic_pressure,
+            rvot_notching: raw.rvot_notching.is_yes(),
+            pr_velocity: raw.pulmonary_regurgitation_max_velocity.map(|v| v.value()),
+            pulmonary_artery: raw.pulmonary_artery.value(),
+            vena: raw.vena.value(),
+            ivc_collapses_over_50: raw.ivc_collapse.is_over_50(),
+            right_atrium_s: raw.right_atrium_s.value(),
+        });
+
+        // Ср.ДЛА выбранным методом; если для него нет данных или результат
+        // нефизиологичен (не выше ДПП, например Mahan при длинном AcT) — по Chemla
+        let chemla: Derived =
+            pulmonary_artery_systolic_pressure.and_then(|s| finite(0.61 * s + 2.0));
+        let mean_pap_by = |method: MeanPapMethod| -> Option<f64> {
+            match method {
+                MeanPapMethod::PrGradient => raw
+                    .pulmonary_regurgitation_max_grad
+                    .map(|v| v.value() + rap),
+                MeanPapMethod::Chemla => chemla.ok(),
+                MeanPapMethod::Mahan => raw
+                    .pulmonary_acceleration_time
+                    .map(|act| 79.0 - 0.45 * act.value()),
+            }
+        };
+        let (pulmonary_artery_med_pressure, mean_pap_method): (Derived, MeanPapMethod) =
+            match mean_pap_by(raw.mean_pap_method).filter(|&v| v.is_finite() && v > rap) {
+                Some(v) => (Ok(v), raw.mean_pap_method),
+                None => (chemla, MeanPapMethod::Chemla),
             };
 
+        // площадь МК по PHT: 220 / PHT
+        let mitral_s_pht: Derived =
+            measured(raw.mitral_presh_time).and_then(|pht| checked_div(220.0, pht, "PHT МК"));
+        let mitral_stenosis = MitralStenosis::assess(&MsInput {
+            mid_grad: raw.mid_grad_mitral_valve.map(|v| v.value()),
+            valve_area: raw.mitral_s_planim.map(|v| v.value()).or(mitral_s_pht.ok()),
+            systolic_pressure,
+        });
+
         // --- сборка результата ---
 
         Self {
@@ -469,6 +1490,7 @@ impl CalculatedReportData {
             name: raw.name.clone(),
             birthday: raw.birthday,
             department: raw.department,
+            rhythm: raw.rhythm,
             cardnum: raw.card_number,
 
             height: raw.height,
@@ -484,6 +1506,7 @@ impl CalculatedReportData {
 
             simpson_end_diastolic_volume: raw.simpson_end_diastolic_volume,
             simpson_end_systolic_volume: raw.simpson_end_systolic_volume,
+            wall_motion: raw.wall_motion,
 
             ascending_aorta_diameter: raw.ascending_aorta_diameter,
 
@@ -497,25 +1520,35 @@ impl CalculatedReportData {
 
             right_ventricle: raw.right_ventricle,
             right_ventricle_baz: raw.right_ventricle_baz,
+            left_ventricle_baz: raw.left_ventricle_baz,
             right_ventricle_medium: raw.right_ventricle_medium, // было: *_full
             right_ventricle_wall_thickness: raw.right_ventricle_wall_thickness, // было: *_full
             tapse: raw.tapse,                                   // было: *_full
             septum_thickness_baz: raw.septum_thickness_baz,     // было: *_full
 
+            aortic_valve_morphology: raw.aortic_valve_morphology,
+            bicuspid_type: raw.bicuspid_type,
+            aortic_prosthesis: raw.aortic_prosthesis,
             shutters_aortal: raw.shutters_aortal,
             opening_amplitude: raw.opening_amplitude,
             max_velocity: raw.max_velocity_aortal, // в Raw это max_velocity_aortal
             max_grad: raw.max_grad_aortal,         // в Raw это max_grad_aortal
 
-            mid_grad: raw.mid_grad,   // было: *_full
-            s_doppler: raw.s_doppler, // было: *_full
-            s_planim: raw.s_planim,   // было: *_full
+            mid_grad: raw.mid_grad, // было: *_full
+            lvot_diameter: raw.lvot_diameter,
+            lvot_vti: raw.lvot_vti,
+            s_doppler,
+            s_planim: raw.s_planim, // было: *_full
 
-            presh_time: raw.presh_time,           // было: *_full
-            vena_contracta: raw.vena_contracta,   // было: *_full
+            aortic_regurgitation: raw.aortic_regurgitation,
+            presh_time: raw.presh_time,
+            vena_contracta: raw.vena_contracta,
+            ar_jet_width_ratio: raw.ar_jet_width_ratio,
+            aortic_flow_reversal: raw.aortic_flow_reversal,
             max_velocity_vt: raw.max_velocity_vt, // было: *_full
             max_grad_vt: raw.max_grad_vt,         // было: *_full
 
+            mitral_prosthesis: raw.mitral_prosthesis,
             shutters_mitral: raw.shutters_mitral,
 
             peak_e: raw.peak_e,
@@ -525,16 +1558,33 @@ impl CalculatedReportData {
 
             e_sept: raw.e_sept,
             e_lat: raw.e_lat,
+            valsalva_e_a_change: raw.valsalva_e_a_change,
+            extended_diastology: raw.extended_diastology,
+            strain: raw.strain,
 
             max_velocity_mitral_valve: raw.max_velocity_mitral_valve, // было: *_full
             max_grad_mitral_valve: raw.max_grad_mitral_valve,         // было: *_full
             mid_grad_mitral_valve: raw.mid_grad_mitral_valve,         // было: *_full
+            mitral_presh_time: raw.mitral_presh_time,
+            mitral_s_planim: raw.mitral_s_planim,
+
+            mitral_regurgitation: raw.mitral_regurgitation,
+            mr_vena_contracta: raw.mr_vena_contracta,
 
             calts_back_sash: raw.calts_back_sash,
             posterior_leaflet_base_calcification: raw.posterior_leaflet_base_calcification,
 
+            tricuspid_prosthesis: raw.tricuspid_prosthesis,
+            shutters_tricuspid: raw.shutters_tricuspid,
+            tricuspid_regurgitation: raw.tricuspid_regurgitation,
+            tricuspid_annulus: raw.tricuspid_annulus,
+            tr_vena_contracta: raw.tr_vena_contracta,
+
             max_velocity_tricuspidal_regurgitation: raw.max_velocity_tricuspidal_regurgitation,
 
+            shutters_pulmonary: raw.shutters_pulmonary,
+            pulmonary_regurgitation: raw.pulmonary_regurgitation,
+
             pulmonary_artery: raw.pulmonary_artery,
 
             max_grad_tricuspidal_regurgitation: raw.max_grad_tricuspidal_regurgitation,
@@ -547,25 +1597,62 @@ impl CalculatedReportData {
 
             pulmonary_regurgitation_max_velocity: raw.pulmonary_regurgitation_max_velocity, // было: *_full
             pulmonary_regurgitation_max_grad: raw.pulmonary_regurgitation_max_grad, // было: *_full
+            pulmonary_regurgitation_end_velocity: raw.pulmonary_regurgitation_end_velocity,
 
             vena: raw.vena,
+            ivc_collapse: raw.ivc_collapse,
+            right_atrium_pressure,
+            right_atrium_pressure_by_ivc: raw.right_atrium_pressure.is_none(),
             effusion: raw.effusion,
 
             // вычисляемые значения
             age,
+            aortic_size,
             body_surface_area,
+            bsa_formula: raw.bsa_formula,
             left_atrium_index,
             ejection_fraction,
             left_ventricle_mass,
             left_ventricle_mass_index,
             relative_wall_thickness,
+            fractional_shortening,
+            teichholz_end_diastolic_volume,
+            teichholz_end_systolic_volume,
+            teichholz_ejection_fraction,
             stroke_volume,
+            stroke_volume_index,
+            shunt: raw.shunt,
+            qp_qs,
             cardiac_output,
             cardiac_index,
             peak_e_div_peak_a,
             e_div_e_aps,
+            aortic_valve_area_index,
+            dimensionless_index,
+            aortic_stenosis,
+            aortic_prosthesis_eoa_index,
+            aortic_prosthesis_function,
+            ar_eroa,
+            ar_regurgitant_volume,
+            aortic_regurgitation_grade,
+            mr_eroa,
+            mr_regurgitant_volume,
+            mitral_regurgitation_grade,
+
+            mitral_prosthesis_function,
+            tricuspid_prosthesis_function,
+            mitral_s_pht,
+            mitral_stenosis,
+            pulmonary_stenosis: PulmonaryStenosis::assess(raw.max_grad_in_pulmonary_artery.value()),
+            diastolic_function,
             pulmonary_artery_systolic_pressure,
-            pulmonary_artery_med_pressure, // было: *_full
+            pulmonary_hypertension,
+            right_ventricle_function,
+            strain_function,
+            wall_motion_analysis,
+            pulmonary_artery_med_pressure,
+            mean_pap_method,
+            beats: raw.beats(),
             today,
         }
     }
@@ -1,10 +1,148 @@
-use crate::promptget::{AutoValue, NumXNum, PreciseNum, RenderToString, render_to_string};
+use crate::bullseye::{self, SEGMENT_NAMES, SEGMENTS, SegmentFill};
+use crate::grading::{
+    AorticProsthesisFunction, AorticSize, AorticStenosis, AvProsthesisFunction, AvProsthesisInput,
+    DiastolicFunction, MitralStenosis, PatientProsthesisMismatch, PulmonaryHypertension,
+    PulmonaryStenosis, RegurgitationGrade, RvFunction, StrainFunction, StrainGrade, StrainInput,
+    WallMotion, WallMotionAnalysis, WallMotionScores,
+};
+use crate::promptget::{
+    AutoValue, Beats, Derived, NotCalculated, NumXNum, PreciseNum, RenderToString, render_derived,
+    render_derived_full, render_to_string,
+};
 use crate::report::{
-    CardNumber, Department, MitVal, PericardialEffusion, TdiRelation, ValveLeaflets, YesNo,
+    AorticValveMorphology, BsaFormula, CardNumber, Department, FlowReversal, IvcCollapse,
+    MeanPapMethod, MitVal, ParavalvularLeak, PericardialEffusion, ProsthesisType,
+    RegurgitationDegree, Rhythm, Sex, ShuntDefect, ShuntDirection, SieversType, TdiRelation,
+    ValveLeaflets, YesNo,
 };
 use chrono::{DateTime, Local, NaiveDate};
 use serde::Serialize;
 
+// протез АК: размер в мм, EOA в см², AcT в мс
+#[derive(Debug, Clone, Copy)]
+pub struct AorticProsthesis {
+    pub kind: ProsthesisType,
+    pub size: PreciseNum,                      // p=0
+    pub eoa: Option<PreciseNum>,               // p=2, optional
+    pub dvi: Option<PreciseNum>,               // p=2, optional
+    pub acceleration_time: Option<PreciseNum>, // p=0, optional
+}
+
+// протез МК/ТК: размер в мм, E в м/с, PHT в мс
+#[derive(Debug, Clone, Copy)]
+pub struct AvProsthesis {
+    pub kind: ProsthesisType,
+    pub size: PreciseNum,               // p=0
+    pub peak_e: PreciseNum,             // p=1
+    pub mid_grad: PreciseNum,           // p=1
+    pub presh_time: Option<PreciseNum>, // p=0, optional
+    pub vti_ratio: Option<PreciseNum>,  // p=1, optional, только для МК
+    pub paravalvular_leak: ParavalvularLeak,
+}
+
+impl AvProsthesis {
+    pub fn as_input(&self) -> AvProsthesisInput {
+        AvProsthesisInput {
+            peak_e: self.peak_e.value(),
+            mid_grad: self.mid_grad.value(),
+            presh_time: self.presh_time.map(|v| v.value()),
+            vti_ratio: self.vti_ratio.map(|v| v.value()),
+            significant_leak: self.paravalvular_leak.is_significant(),
+        }
+    }
+}
+
+// допустимое расхождение ФВ по Тейхольцу и по Симпсону, %
+const EF_DISCREPANCY_LIMIT: f64 = 10.0;
+
+// внутрисердечный шунт: диаметры и размер дефекта в см, VTI в см
+#[derive(Debug, Clone, Copy)]
+pub struct Shunt {
+    pub defect: ShuntDefect,
+    pub defect_size: Option<PreciseNum>, // p=1, optional
+    pub direction: ShuntDirection,
+    pub rvot_diameter: PreciseNum, // p=1
+    pub rvot_vti: PreciseNum,      // p=1
+    pub lvot_diameter: PreciseNum, // p=1, берется из УО по допплеру, если введен
+    pub lvot_vti: PreciseNum,      // p=1
+}
+
+// деформация (speckle tracking), %: GLS, сегменты ЛЖ и свободная стенка ПЖ отрицательные
+#[derive(Debug, Clone, Copy)]
+pub struct Strain {
+    pub gls: PreciseNum,                                  // p=1
+    pub segments: Option<[Option<PreciseNum>; SEGMENTS]>, // p=0, optional
+    pub rv_free_wall: Option<PreciseNum>,                 // p=1, optional
+    // p=0; если уже введена в доп. диастолической функции — берется оттуда
+    pub la_reservoir: Option<PreciseNum>,
+}
+
+impl Strain {
+    pub fn as_input(&self) -> StrainInput {
+        StrainInput {
+            gls: self.gls.value(),
+            segments: self.segments.map(|s| s.map(|v| v.map(|v| v.value()))),
+            rv_free_wall: self.rv_free_wall.map(|v| v.value()),
+            la_reservoir: self.la_reservoir.map(|v| v.value()),
+        }
+    }
+}
+
+// дополнительная диастолическая функция: время в мс, скорости в см/с, деформация в %
+#[derive(Debug, Clone, Copy)]
+pub struct ExtendedDiastology {
+    pub deceleration_time: Option<PreciseNum>, // p=0, optional
+    pub isovolumic_relaxation_time: Option<PreciseNum>, // p=0, optional
+    pub pv_systolic: Option<PreciseNum>,       // p=0, optional
+    pub pv_diastolic: Option<PreciseNum>,      // p=0, только если введена S
+    pub pv_atrial_reversal: Option<PreciseNum>, // p=0, optional
+    pub ar_a_duration: Option<PreciseNum>,     // p=0, только если введена Ar
+    pub la_reservoir_strain: Option<PreciseNum>, // p=0, optional
+}
+
+impl ExtendedDiastology {
+    pub fn pv_s_d_ratio(&self) -> Option<f64> {
+        match (self.pv_systolic, self.pv_diastolic) {
+            (Some(s), Some(d)) if d.value() > 0.0 => Some(s.value() / d.value()),
+            _ => None,
+        }
+    }
+}
+
+impl RenderToString for ExtendedDiastology {
+    fn render_to_string(&self) -> String {
+        let mut params: Vec<String> = Vec::new();
+        if let Some(dt) = self.deceleration_time {
+            params.push(format!("DT {} мс (N 160-240 мс)", dt));
+        }
+        if let Some(ivrt) = self.isovolumic_relaxation_time {
+            params.push(format!("IVRT {} мс (N 70-100 мс)", ivrt));
+        }
+        if let (Some(s), Some(d)) = (self.pv_systolic, self.pv_diastolic) {
+            params.push(format!("кровоток в ЛВ: S {} см/с, D {} см/с", s, d));
+        }
+        if let Some(ratio) = self.pv_s_d_ratio() {
+            params.push(format!("S/D {}", PreciseNum::from_float(ratio, 1)));
+        }
+        if let Some(ar) = self.pv_atrial_reversal {
+            params.push(format!("Ar {} см/с", ar));
+        }
+        if let Some(d) = self.ar_a_duration {
+            params.push(format!("Ar − A {} мс (N<30 мс)", d));
+        }
+        if let Some(la) = self.la_reservoir_strain {
+            params.push(format!("резервуарная деформация ЛП {} % (N>18 %)", la));
+        }
+        if params.is_empty() {
+            return "".to_owned();
+        }
+        format!(
+            "Дополнительная диастолическая функция: {}. ",
+            params.join(", ")
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RawReportData {
     // 1) ФИО
@@ -13,20 +151,30 @@ pub struct RawReportData {
     // 2) Дата рождения
     pub birthday: NaiveDate,
 
+    // 2.1) Пол
+    pub sex: Sex,
+
     // 3) Отделение
     pub department: Department,
 
     // 4) АК№/ИБ№ (вводится в зависимости от отделения)
     pub card_number: CardNumber,
 
+    // 4.1) Ритм (при ФП без волны А, скорости усредняются по циклам)
+    pub rhythm: Rhythm,
+
     // 5) Рост
     pub height: PreciseNum, // p=0
 
     // 6) Вес
     pub weight: PreciseNum, // p=0
 
+    // 6.1) Формула ППТ (из настроек или выбор при обследовании)
+    pub bsa_formula: BsaFormula,
+
     // 7) ЧСС
     pub pulse: PreciseNum, // p=0
+    pub pulse_beats: Option<Beats>,
 
     // 8) Ао (p=1)
     pub aortic_sinus_diameter: PreciseNum,
@@ -58,6 +206,9 @@ pub struct RawReportData {
     // 17) ПЖ баз (p=1)
     pub right_ventricle_baz: PreciseNum,
 
+    // 17.1) ЛЖ баз (p=1, optional)
+    pub left_ventricle_baz: Option<PreciseNum>,
+
     // 18) ПЖ ср (p=1, optional)
     pub right_ventricle_medium: Option<PreciseNum>,
 
@@ -66,12 +217,27 @@ pub struct RawReportData {
 
     // 20) TAPSE (p=1, optional)
     pub tapse: Option<PreciseNum>,
+    pub tapse_beats: Option<Beats>,
+
+    // 20.1) S ПЖ в диастолу, см² (p=1, optional)
+    pub right_ventricle_diastolic_area: Option<PreciseNum>,
+
+    // 20.2) S ПЖ в систолу, см² (p=1, только если введена диастолическая)
+    pub right_ventricle_systolic_area: Option<PreciseNum>,
+
+    // 20.3) S' ТК (TDI), см/с (p=1, optional)
+    pub tricuspid_s_prime: Option<PreciseNum>,
+
+    // 20.4) RIMP ПЖ (TDI) (p=2, optional)
+    pub right_ventricle_rimp: Option<PreciseNum>,
 
     // 21) КДР (p=1)
     pub left_ventricle_diastolic_size: PreciseNum,
+    pub left_ventricle_diastolic_size_beats: Option<Beats>,
 
     // 22) КСР (p=1)
     pub left_ventricle_systolic_size: PreciseNum,
+    pub left_ventricle_systolic_size_beats: Option<Beats>,
 
     // 23) МЖП (p=1)
     pub septum_thickness: PreciseNum,
@@ -84,21 +250,40 @@ pub struct RawReportData {
 
     // 26) КДО по Симпсону (p=0)
     pub simpson_end_diastolic_volume: PreciseNum,
+    pub simpson_end_diastolic_volume_beats: Option<Beats>,
 
     // 27) КСО по Симпсону (p=0)
     pub simpson_end_systolic_volume: PreciseNum,
+    pub simpson_end_systolic_volume_beats: Option<Beats>,
+
+    // 27.1) Локальная сократимость ЛЖ: баллы 17 сегментов (можно пропустить)
+    pub wall_motion: Option<WallMotionScores>,
+
+    // 28) Диаметр ВТЛЖ (p=1, optional; вместе с VTI ВТЛЖ дает УО по допплеру)
+    pub lvot_diameter: Option<PreciseNum>,
+
+    // 28.01) VTI ВТЛЖ (p=1, спрашивается только если введен диаметр ВТЛЖ)
+    pub lvot_vti: Option<PreciseNum>,
+    pub lvot_vti_beats: Option<Beats>,
+
+    // 28.1) Морфология АК
+    pub aortic_valve_morphology: AorticValveMorphology,
+
+    // 28.2) Тип двустворчатого АК по Sievers (только если морфология = двустворчатый)
+    pub bicuspid_type: Option<SieversType>,
 
-    // 28) УО (p=0, optional)
-    pub stroke_volume: Option<PreciseNum>,
+    // 28.3) Протез АК (только если морфология = протез)
+    pub aortic_prosthesis: Option<AorticProsthesis>,
 
-    // 29) АК (состояние створок)
-    pub shutters_aortal: ValveLeaflets,
+    // 29) АК (состояние створок, не спрашивается при механическом протезе)
+    pub shutters_aortal: Option<ValveLeaflets>,
 
     // 30) Амплитуда раскрытия (p=1)
     pub opening_amplitude: PreciseNum,
 
     // 31) Макс скорость (p=1)
     pub max_velocity_aortal: PreciseNum,
+    pub max_velocity_aortal_beats: Option<Beats>,
 
     // 32) Макс градиент (p=0)
     pub max_grad_aortal: PreciseNum,
@@ -109,26 +294,50 @@ pub struct RawReportData {
     // 34) Средний градиент (p=0, только если stenosis=Yes)
     pub mid_grad: Option<PreciseNum>,
 
-    // 35) Площадь по допплеру (p=1, только если stenosis=Yes)
-    pub s_doppler: Option<PreciseNum>,
+    // 35) VTI АК (p=1, только если stenosis=Yes и введен диаметр ВТЛЖ)
+    pub aortic_vti: Option<PreciseNum>,
 
     // 36) Площадь планиметрически (p=1, только если stenosis=Yes)
     pub s_planim: Option<PreciseNum>,
 
-    // 37) PHT (p=0, optional)
+    // 36.1) Аортальная регургитация (Да/Нет)
+    pub aortic_regurgitation: YesNo,
+
+    // 37) PHT (p=0, optional, только если aortic_regurgitation=Yes)
     pub presh_time: Option<PreciseNum>,
 
-    // 38) VC АР (p=1, спрашивается только если presh_time.is_some())
+    // 38) VC АР (p=1, optional, только если aortic_regurgitation=Yes)
     pub vena_contracta: Option<PreciseNum>,
 
+    // 38.1) Ширина струи АР / ВТЛЖ, % (p=0, optional, только если aortic_regurgitation=Yes)
+    pub ar_jet_width_ratio: Option<PreciseNum>,
+
+    // 38.2) Ретроградный кровоток в нисходящей аорте (только если aortic_regurgitation=Yes)
+    pub aortic_flow_reversal: Option<FlowReversal>,
+
+    // 38.3) Радиус PISA АР (p=2, optional, только если aortic_regurgitation=Yes)
+    pub ar_pisa_radius: Option<PreciseNum>,
+
+    // 38.4) Скорость алиасинга PISA АР, см/с (p=0, только если введен радиус PISA)
+    pub ar_aliasing_velocity: Option<PreciseNum>,
+
+    // 38.5) V max АР, м/с (p=1, только если введен радиус PISA)
+    pub ar_max_velocity: Option<PreciseNum>,
+
+    // 38.6) VTI АР (p=0, только если введен радиус PISA)
+    pub ar_vti: Option<PreciseNum>,
+
     // 39) ВТЛЖ Макс скорость (p=1, спрашивается только если septum_thickness_baz.is_some())
     pub max_velocity_vt: Option<PreciseNum>,
 
     // 40) ВТЛЖ макс градиент (p=0, спрашивается только если max_velocity_vt.is_some())
     pub max_grad_vt: Option<PreciseNum>,
 
-    // 41) МК (состояние створок)
-    pub shutters_mitral: ValveLeaflets,
+    // 40.1) Протез МК (только если выбран протез)
+    pub mitral_prosthesis: Option<AvProsthesis>,
+
+    // 41) МК (состояние створок, не спрашивается при механическом протезе)
+    pub shutters_mitral: Option<ValveLeaflets>,
 
     // 42) Кальцинат в основании задней створки (Да/Нет)
     pub calts_back_sash: YesNo,
@@ -136,39 +345,104 @@ pub struct RawReportData {
     // 43) Кальциноз основания задней створки/кольца (Да/Нет)
     pub posterior_leaflet_base_calcification: YesNo,
 
-    // 44) МК: E (p=0)
-    pub peak_e: PreciseNum,
+    // 44) МК: E (p=0, не спрашивается при протезе МК)
+    pub peak_e: Option<PreciseNum>,
+    pub peak_e_beats: Option<Beats>,
+
+    // 45) A (p=0, не спрашивается при протезе МК)
+    pub peak_a: Option<PreciseNum>,
+    pub peak_a_beats: Option<Beats>,
+
+    // 46) TDI (e<a / e>a), не спрашивается при протезе МК
+    pub tdi_vel: Option<TdiRelation>,
+
+    // 47) E sept (p=0, не спрашивается при протезе МК)
+    pub e_sept: Option<PreciseNum>,
+    pub e_sept_beats: Option<Beats>,
+
+    // 48) E’ lat (p=0, не спрашивается при протезе МК)
+    pub e_lat: Option<PreciseNum>,
+    pub e_lat_beats: Option<Beats>,
 
-    // 45) A (p=0)
-    pub peak_a: PreciseNum,
+    // 48.2) Δ E/A при пробе Вальсальвы (p=1, optional)
+    pub valsalva_e_a_change: Option<PreciseNum>,
 
-    // 46) TDI (e<a / e>a) — у тебя строкой, оставим строкой
-    pub tdi_vel: TdiRelation,
+    // 48.3) Поражение миокарда при сохранной ФВ (Да/Нет)
+    pub myocardial_disease: YesNo,
 
-    // 47) E sept (p=0)
-    pub e_sept: PreciseNum,
+    // 48.4) Дополнительная диастолическая функция: DT, IVRT, ЛВ, деформация ЛП (можно пропустить)
+    pub extended_diastology: Option<ExtendedDiastology>,
 
-    // 48) E’ lat (p=0)
-    pub e_lat: PreciseNum,
+    // 48.5) Деформация (speckle tracking): GLS, 17 сегментов, ПЖ, ЛП (можно пропустить)
+    pub strain: Option<Strain>,
 
     // 49) МК Макс скорость (p=1, optional)
     pub max_velocity_mitral_valve: Option<PreciseNum>,
+    pub max_velocity_mitral_valve_beats: Option<Beats>,
 
     // 50) МК Макс градиент (p=1, зависит от max_velocity_mitral_valve)
     pub max_grad_mitral_valve: Option<PreciseNum>,
 
     // 51) МК Средний градиент (p=1, зависит от max_velocity_mitral_valve)
     pub mid_grad_mitral_valve: Option<PreciseNum>,
+    pub mid_grad_mitral_valve_beats: Option<Beats>,
+
+    // 51.01) МК PHT (p=0, optional, зависит от max_velocity_mitral_valve)
+    pub mitral_presh_time: Option<PreciseNum>,
+    pub mitral_presh_time_beats: Option<Beats>,
+
+    // 51.02) МК площадь планиметрически (p=1, optional, зависит от max_velocity_mitral_valve)
+    pub mitral_s_planim: Option<PreciseNum>,
+
+    // 51.1) Митральная регургитация (Да/Нет)
+    pub mitral_regurgitation: YesNo,
+
+    // 51.2) VC МР (p=1, optional, только если mitral_regurgitation=Yes)
+    pub mr_vena_contracta: Option<PreciseNum>,
+
+    // 51.3) Радиус PISA МР (p=2, optional, только если mitral_regurgitation=Yes)
+    pub mr_pisa_radius: Option<PreciseNum>,
+
+    // 51.4) Скорость алиасинга PISA МР, см/с (p=0, только если введен радиус PISA)
+    pub mr_aliasing_velocity: Option<PreciseNum>,
+
+    // 51.5) V max МР, м/с (p=1, только если введен радиус PISA)
+    pub mr_max_velocity: Option<PreciseNum>,
+
+    // 51.6) VTI МР (p=0, только если введен радиус PISA)
+    pub mr_vti: Option<PreciseNum>,
+
+    // 51.61) Протез ТК (только если выбран протез)
+    pub tricuspid_prosthesis: Option<AvProsthesis>,
+
+    // 51.7) ТК (состояние створок, не спрашивается при механическом протезе)
+    pub shutters_tricuspid: Option<ValveLeaflets>,
+
+    // 51.8) Трикуспидальная регургитация (степень)
+    pub tricuspid_regurgitation: RegurgitationDegree,
+
+    // 51.9) Фиброзное кольцо ТК (p=1, optional, не спрашивается при протезе)
+    pub tricuspid_annulus: Option<PreciseNum>,
+
+    // 51.10) VC ТР (p=1, optional, только если есть регургитация)
+    pub tr_vena_contracta: Option<PreciseNum>,
 
     // 52) ТК Макс скорость ТР (p=1)
     pub max_velocity_tricuspidal_regurgitation: PreciseNum,
+    pub max_velocity_tricuspidal_regurgitation_beats: Option<Beats>,
 
     // 53) ТК макс градиент ТР (p=0)
     pub max_grad_tricuspidal_regurgitation: PreciseNum,
 
-    // 54) "прибавить 3?" (да / иное)
+    // 54) ДПП для СДЛА: None — оценка по НПВ, Some — введено вручную ("иное")
     pub right_atrium_pressure: Option<i64>,
 
+    // 54.1) ЛК (состояние створок)
+    pub shutters_pulmonary: ValveLeaflets,
+
+    // 54.2) Пульмональная регургитация (степень)
+    pub pulmonary_regurgitation: RegurgitationDegree,
+
     // 55) Диаметр ЛА (p=1)
     pub pulmonary_artery: PreciseNum,
 
@@ -180,18 +454,43 @@ pub struct RawReportData {
 
     // 58) ЛА макс скорость (p=1)
     pub max_velocity_in_pulmonary_artery: PreciseNum,
+    pub max_velocity_in_pulmonary_artery_beats: Option<Beats>,
 
     // 59) ЛА макс градиент (p=0)
     pub max_grad_in_pulmonary_artery: PreciseNum,
 
-    // 60) ЛР макс. скорость (p=1, optional)
+    // 60) ЛР макс. скорость (p=1, optional, только если есть регургитация)
     pub pulmonary_regurgitation_max_velocity: Option<PreciseNum>,
+    pub pulmonary_regurgitation_max_velocity_beats: Option<Beats>,
 
     // 61) ЛР макс градиент (p=0, спрашивается только если скорость ЛР введена)
     pub pulmonary_regurgitation_max_grad: Option<PreciseNum>,
 
-    // 62) НПВ (p=1)
+    // 61.01) ЛР конечнодиастолическая скорость (p=1, optional, только если есть регургитация)
+    pub pulmonary_regurgitation_end_velocity: Option<PreciseNum>,
+    pub pulmonary_regurgitation_end_velocity_beats: Option<Beats>,
+
+    // 61.02) AcT ЛА, мс (p=0, optional, для формулы Mahan)
+    pub pulmonary_acceleration_time: Option<PreciseNum>,
+
+    // 61.03) Метод расчета Ср.ДЛА
+    pub mean_pap_method: MeanPapMethod,
+
+    // 61.04) Внутрисердечный шунт (только если есть)
+    pub shunt: Option<Shunt>,
+
+    // 61.1) Уплощение МЖП (Да/Нет)
+    pub septum_flattening: YesNo,
+
+    // 61.2) Среднесистолическая выемка на потоке в ВТПЖ (Да/Нет)
+    pub rvot_notching: YesNo,
+
+    // 62) НПВ (p=1, спрашивается перед ДПП)
     pub vena: PreciseNum,
+    pub vena_beats: Option<Beats>,
+
+    // 62.1) Коллабирование НПВ на вдохе (более/менее 50%)
+    pub ivc_collapse: IvcCollapse,
 
     // 63) Перикардиальный выпот (выбор из списка)
     pub effusion: PericardialEffusion,
@@ -204,6 +503,7 @@ pub struct CalculatedReportData {
     pub birthday: NaiveDate,
     pub department: Department,
     pub cardnum: CardNumber,
+    pub rhythm: Rhythm,
 
     pub height: PreciseNum, // p=0
     pub weight: PreciseNum, // p=0
@@ -218,6 +518,7 @@ pub struct CalculatedReportData {
 
     pub simpson_end_diastolic_volume: PreciseNum, // p=0
     pub simpson_end_systolic_volume: PreciseNum,  // p=0
+    pub wall_motion: Option<WallMotionScores>,
 
     pub ascending_aorta_diameter: PreciseNum, // p=1
 
@@ -225,11 +526,12 @@ pub struct CalculatedReportData {
     pub left_atrium4: NumXNum,          // p=1
     pub left_atrium_volume: PreciseNum, // p=0
 
-    pub right_atrium_s: PreciseNum,      // p=0
-    pub right_atrium4: NumXNum,          // p=1
-    pub right_atrium_volume: PreciseNum, // p=0
-    pub right_ventricle: PreciseNum,     // p=1
-    pub right_ventricle_baz: PreciseNum, // p=1
+    pub right_atrium_s: PreciseNum,             // p=0
+    pub right_atrium4: NumXNum,                 // p=1
+    pub right_atrium_volume: PreciseNum,        // p=0
+    pub right_ventricle: PreciseNum,            // p=1
+    pub right_ventricle_baz: PreciseNum,        // p=1
+    pub left_ventricle_baz: Option<PreciseNum>, // p=1, optional
     // было: right_ventricle_medium_full
     pub right_ventricle_medium: Option<PreciseNum>, // p=1, optional
     // было: right_ventricle_wall_thickness_full
@@ -239,37 +541,50 @@ pub struct CalculatedReportData {
     // было: septum_thickness_baz_full
     pub septum_thickness_baz: Option<PreciseNum>, // p=1, optional
 
-    pub shutters_aortal: ValveLeaflets,
+    pub aortic_valve_morphology: AorticValveMorphology,
+    pub bicuspid_type: Option<SieversType>,
+    pub aortic_prosthesis: Option<AorticProsthesis>,
+    pub shutters_aortal: Option<ValveLeaflets>,
     pub opening_amplitude: PreciseNum, // p=1
     pub max_velocity: PreciseNum,      // p=1 (это max_velocity_aortal)
     pub max_grad: PreciseNum,          // p=0 (это max_grad_aortal)
 
     // было: mid_grad_full
     pub mid_grad: Option<PreciseNum>, // p=0, only if stenosis=Yes
-    // было: s_doppler_full
-    pub s_doppler: Option<PreciseNum>, // p=1, only if stenosis=Yes
+    pub lvot_diameter: Option<PreciseNum>, // p=1, optional
+    pub lvot_vti: Option<PreciseNum>, // p=1, only if lvot_diameter.is_some()
+    // было: s_doppler_full, теперь по уравнению непрерывности
+    pub s_doppler: Derived, // p=2, only if lvot_diameter.is_some()
     // было: s_planim_full
     pub s_planim: Option<PreciseNum>, // p=1, only if stenosis=Yes
 
+    pub aortic_regurgitation: YesNo,
     // было: presh_time_full
     pub presh_time: Option<PreciseNum>, // p=0, optional
     // было: vena_contracta_full
-    pub vena_contracta: Option<PreciseNum>, // p=1, only if presh_time.is_some()
+    pub vena_contracta: Option<PreciseNum>, // p=1, optional
+    pub ar_jet_width_ratio: Option<PreciseNum>, // p=0, optional
+    pub aortic_flow_reversal: Option<FlowReversal>,
 
     // было: max_velocity_vt_full
     pub max_velocity_vt: Option<PreciseNum>, // p=1, optional (only if septum_thickness_baz.is_some())
     // было: max_grad_vt_full
     pub max_grad_vt: Option<PreciseNum>, // p=0, only if max_velocity_vt.is_some()
 
-    pub shutters_mitral: ValveLeaflets,
+    pub mitral_prosthesis: Option<AvProsthesis>,
+    pub shutters_mitral: Option<ValveLeaflets>,
+
+    pub peak_e: Option<PreciseNum>, // p=0
+    pub peak_a: Option<PreciseNum>, // p=0
 
-    pub peak_e: PreciseNum, // p=0
-    pub peak_a: PreciseNum, // p=0
+    pub tdi_vel: Option<TdiRelation>,
 
-    pub tdi_vel: TdiRelation,
+    pub e_sept: Option<PreciseNum>, // p=0
+    pub e_lat: Option<PreciseNum>,  // p=0
 
-    pub e_sept: PreciseNum, // p=0
-    pub e_lat: PreciseNum,  // p=0
+    pub valsalva_e_a_change: Option<PreciseNum>, // p=1, optional
+    pub extended_diastology: Option<ExtendedDiastology>,
+    pub strain: Option<Strain>,
 
     // было: max_velocity_mitral_valve_full
     pub max_velocity_mitral_valve: Option<PreciseNum>, // p=1, optional
@@ -277,12 +592,26 @@ pub struct CalculatedReportData {
     pub max_grad_mitral_valve: Option<PreciseNum>, // p=1, depends on max_velocity_mitral_valve
     // было: mid_grad_mitral_valve_full
     pub mid_grad_mitral_valve: Option<PreciseNum>, // p=1, depends on max_velocity_mitral_valve
+    pub mitral_presh_time: Option<PreciseNum>,     // p=0, depends on max_velocity_mitral_valve
+    pub mitral_s_planim: Option<PreciseNum>,       // p=1, depends on max_velocity_mitral_valve
+
+    pub mitral_regurgitation: YesNo,
+    pub mr_vena_contracta: Option<PreciseNum>, // p=1, optional
 
     pub calts_back_sash: YesNo,
     pub posterior_leaflet_base_calcification: YesNo,
 
+    pub tricuspid_prosthesis: Option<AvProsthesis>,
+    pub shutters_tricuspid: Option<ValveLeaflets>,
+    pub tricuspid_regurgitation: RegurgitationDegree,
+    pub tricuspid_annulus: Option<PreciseNum>, // p=1, optional
+    pub tr_vena_contracta: Option<PreciseNum>, // p=1, optional
+
     pub max_velocity_tricuspidal_regurgitation: PreciseNum, // p=1
 
+    pub shutters_pulmonary: ValveLeaflets,
+    pub pulmonary_regurgitation: RegurgitationDegree,
+
     pub pulmonary_artery: PreciseNum, // p=1
 
     pub max_grad_tricuspidal_regurgitation: PreciseNum, // p=0
@@ -299,44 +628,380 @@ pub struct CalculatedReportData {
     pub pulmonary_regurgitation_max_velocity: Option<PreciseNum>, // p=1, optional
     // было: pulmonary_regurgitation_max_grad_full
     pub pulmonary_regurgitation_max_grad: Option<PreciseNum>, // p=0, only if max_velocity.is_some()
+    pub pulmonary_regurgitation_end_velocity: Option<PreciseNum>, // p=1, optional
 
     pub vena: PreciseNum, // p=1
+    pub ivc_collapse: IvcCollapse,
+    pub right_atrium_pressure: i64,
+    pub right_atrium_pressure_by_ivc: bool,
 
     pub effusion: PericardialEffusion,
 
     // --- рассчитываемые значения (внизу структуры) ---
     pub age: i32,
+    // производные величины с делением — Derived: значение или причина, почему не рассчитано
+    pub aortic_size: Result<AorticSize, NotCalculated>,
+
+    pub left_atrium_index: Derived,
+    pub body_surface_area: Derived,
+    pub bsa_formula: BsaFormula,
+    pub ejection_fraction: Derived,
 
-    pub left_atrium_index: f64,
-    pub body_surface_area: f64,
-    pub ejection_fraction: f64,
+    pub left_ventricle_mass: Derived,
+    pub left_ventricle_mass_index: Derived,
+    pub relative_wall_thickness: Derived,
 
-    pub left_ventricle_mass: f64,
-    pub left_ventricle_mass_index: f64,
-    pub relative_wall_thickness: f64,
+    pub fractional_shortening: Derived,
+    pub teichholz_end_diastolic_volume: Derived,
+    pub teichholz_end_systolic_volume: Derived,
+    pub teichholz_ejection_fraction: Derived,
 
     pub stroke_volume: AutoValue,
+    pub stroke_volume_index: Derived,
+
+    pub shunt: Option<Shunt>,
+    pub qp_qs: Derived,
+
+    pub cardiac_output: Derived,
+    pub cardiac_index: Derived,
+
+    pub peak_e_div_peak_a: Derived,
+    pub e_div_e_aps: Derived,
 
-    pub cardiac_output: f64,
-    pub cardiac_index: f64,
+    pub aortic_valve_area_index: Derived,
+    pub dimensionless_index: Derived,
+    pub aortic_stenosis: AorticStenosis,
+    pub aortic_prosthesis_eoa_index: Derived,
+    pub aortic_prosthesis_function: Option<AorticProsthesisFunction>,
 
-    pub peak_e_div_peak_a: f64,
-    pub e_div_e_aps: f64,
+    pub ar_eroa: Derived,
+    pub ar_regurgitant_volume: Derived,
+    pub aortic_regurgitation_grade: Option<RegurgitationGrade>,
 
-    pub pulmonary_artery_systolic_pressure: f64,
-    // было: pulmonary_artery_med_pressure_full
-    pub pulmonary_artery_med_pressure: Option<PreciseNum>,
+    pub mr_eroa: Derived,
+    pub mr_regurgitant_volume: Derived,
+    pub mitral_regurgitation_grade: Option<RegurgitationGrade>,
+
+    pub mitral_prosthesis_function: Option<AvProsthesisFunction>,
+    pub tricuspid_prosthesis_function: Option<AvProsthesisFunction>,
+
+    pub mitral_s_pht: Derived,
+    pub mitral_stenosis: MitralStenosis,
+    pub pulmonary_stenosis: PulmonaryStenosis,
+
+    pub diastolic_function: Option<DiastolicFunction>,
+
+    pub pulmonary_artery_systolic_pressure: Derived,
+    pub pulmonary_hypertension: PulmonaryHypertension,
+    pub right_ventricle_function: RvFunction,
+    pub strain_function: Option<StrainFunction>,
+    pub wall_motion_analysis: Option<WallMotionAnalysis>,
+    // было: pulmonary_artery_med_pressure_full, теперь выбранным методом
+    pub pulmonary_artery_med_pressure: Derived, // p=0
+    pub mean_pap_method: MeanPapMethod,
+    pub beats: Vec<Beats>,
 
     pub today: DateTime<Local>,
 }
 
 impl CalculatedReportData {
+    fn render_aortic_valve_morphology(&self) -> String {
+        let morphology = match (self.bicuspid_type, self.aortic_prosthesis) {
+            (Some(t), _) => format!("{} ({})", self.aortic_valve_morphology, t),
+            (_, Some(p)) => format!(
+                "{} ({}, размер {} мм)",
+                self.aortic_valve_morphology, p.kind, p.size
+            ),
+            _ => self.aortic_valve_morphology.to_string(),
+        };
+        match self.shutters_aortal {
+            Some(_) => format!("{}, ", morphology),
+            None => format!("{}. ", morphology),
+        }
+    }
+
+    fn render_aortic_prosthesis(&self) -> String {
+        let (Some(p), Some(function)) = (self.aortic_prosthesis, self.aortic_prosthesis_function)
+        else {
+            return "".to_owned();
+        };
+        let mut params: Vec<String> = Vec::new();
+        if let Some(eoa) = p.eoa {
+            params.push(format!("EOA {} см²", eoa));
+        }
+        if let Some(r) = function.reference_eoa {
+            params.push(format!(
+                "эталонная EOA {} см²",
+                PreciseNum::from_float(r, 1)
+            ));
+        }
+        match self.aortic_prosthesis_eoa_index {
+            Ok(i) => params.push(format!("EOAi {} см²/м²", PreciseNum::from_float(i, 2))),
+            Err(NotCalculated::Missing) => {}
+            Err(_) => params.push("EOAi не рассчитан".to_owned()),
+        }
+        if let Some(dvi) = p.dvi {
+            params.push(format!("DVI {}", dvi));
+        }
+        if let Some(at) = p.acceleration_time {
+            params.push(format!("AcT {} мс", at));
+        }
+        let mut out = " Протез АК".to_owned();
+        if !params.is_empty() {
+            out.push_str(&format!(": {}", params.join(", ")));
+        }
+        out.push_str(&format!(" — {}", function.grade));
+        if let Some(m) = function.mismatch
+            && m != PatientProsthesisMismatch::None
+        {
+            out.push_str(&format!("; {}", m));
+        }
+        out.push('.');
+        out
+    }
+
+    // измерения, введённые по нескольким циклам
+    fn render_beats(&self) -> String {
+        if self.beats.is_empty() {
+            return "".to_owned();
+        }
+        let averaged: Vec<String> = self.beats.iter().map(|b| b.render_to_string()).collect();
+        format!("Усреднение по циклам: {}.", averaged.join("; "))
+    }
+
+    fn render_rhythm(&self) -> String {
+        if self.rhythm.is_af() && !self.beats.is_empty() {
+            format!(
+                "{} (скоростные показатели усреднены по нескольким циклам)",
+                self.rhythm
+            )
+        } else {
+            self.rhythm.to_string()
+        }
+    }
+
+    fn render_mitral_inflow(&self) -> String {
+        // при ФП волны А нет — только Е
+        if let (Some(e), None) = (self.peak_e, self.peak_a) {
+            return format!("Peak E {} см/с (N  48-86). ", e);
+        }
+        let (Some(e), Some(a)) = (self.peak_e, self.peak_a) else {
+            return "".to_owned();
+        };
+        format!(
+            "Peak E {} см/с (N  48-86), Peak А {} см/с (N 45-73), E/A {}{}. ",
+            e,
+            a,
+            render_derived(self.peak_e_div_peak_a, 1),
+            render_to_string(
+                self.valsalva_e_a_change,
+                ", Δ E/A при пробе Вальсальвы ",
+                "",
+            ),
+        )
+    }
+
+    fn render_tdi(&self) -> String {
+        let (Some(sept), Some(lat)) = (self.e_sept, self.e_lat) else {
+            return "".to_owned();
+        };
+        format!(
+            "{}E’ sept - {} см/с(N>8). E’ lat - {} см/с , Е/е' {}. ",
+            match self.tdi_vel {
+                Some(tdi) => format!("ТDI {}. ", tdi),
+                None => "".to_owned(),
+            },
+            sept,
+            lat,
+            render_derived(self.e_div_e_aps, 1)
+        )
+    }
+
+    fn render_strain(&self) -> String {
+        let (Some(s), Some(function)) = (self.strain, &self.strain_function) else {
+            return "".to_owned();
+        };
+        let flag = |reduced: bool| if reduced { " — снижена" } else { "" };
+        let mut out = format!(
+            "Деформация миокарда (speckle tracking): GLS ЛЖ {} % (N ≤ −18 %) — {}",
+            s.gls, function.gls
+        );
+        if !function.reduced_segments.is_empty() {
+            let numbers: Vec<String> = function
+                .reduced_segments
+                .iter()
+                .map(|&n| format!("{} ({})", n, SEGMENT_NAMES[n - 1]))
+                .collect();
+            out.push_str(&format!(
+                "; снижение региональной деформации в сегментах {}",
+                numbers.join(", ")
+            ));
+        } else if s.segments.is_some() {
+            out.push_str("; региональная деформация сохранена");
+        }
+        if let (Some(rv), Some(reduced)) = (s.rv_free_wall, function.rv_reduced) {
+            out.push_str(&format!(
+                "; деформация свободной стенки ПЖ {} % (N ≤ −20 %){}",
+                rv,
+                flag(reduced)
+            ));
+        }
+        // уже описана в дополнительной диастолической функции
+        let la_in_diastology = self
+            .extended_diastology
+            .is_some_and(|d| d.la_reservoir_strain.is_some());
+        if let (Some(la), Some(reduced), false) =
+            (s.la_reservoir, function.la_reduced, la_in_diastology)
+        {
+            out.push_str(&format!(
+                "; резервуарная деформация ЛП {} % (N>18 %){}",
+                la,
+                flag(reduced)
+            ));
+        }
+        out.push_str(". ");
+        out
+    }
+
+    // диаграмма «бычий глаз» региональной деформации ЛЖ
+    fn render_strain_bullseye(&self) -> String {
+        let Some(segments) = self.strain.and_then(|s| s.segments) else {
+            return "".to_owned();
+        };
+        let fills = segments.map(|v| match v {
+            Some(v) => SegmentFill {
+                color: if v.value() >= 0.0 {
+                    [90, 120, 220]
+                } else {
+                    match StrainGrade::of_left_ventricle(v.value()) {
+                        StrainGrade::Normal => [200, 40, 40],
+                        StrainGrade::Borderline => [235, 120, 120],
+                        StrainGrade::Reduced => [250, 200, 200],
+                    }
+                },
+                label: v.to_string(),
+            },
+            None => SegmentFill {
+                color: [200, 200, 200],
+                label: "".to_owned(),
+            },
+        });
+        bullseye::render_base64(&fills)
+    }
+
+    fn render_shunt(&self) -> String {
+        let Some(s) = self.shunt else {
+            return "".to_owned();
+        };
+        format!(
+            "Шунт: {}{}, сброс {}. Qp/Qs {}{} (D ВТПЖ {} см, VTI ВТПЖ {} см; D ВТЛЖ {} см, VTI ВТЛЖ {} см).",
+            s.defect,
+            render_to_string(s.defect_size, " ", " см"),
+            s.direction,
+            render_derived(self.qp_qs, 1),
+            match self.qp_qs {
+                Ok(qp_qs) if qp_qs >= 1.5 => " — гемодинамически значимый сброс",
+                Ok(_) => "",
+                Err(_) => " (не рассчитано)",
+            },
+            s.rvot_diameter,
+            s.rvot_vti,
+            s.lvot_diameter,
+            s.lvot_vti
+        )
+    }
+
+    fn render_aortic_regurgitation(&self) -> String {
+        if !self.aortic_regurgitation.is_yes() {
+            return "".to_owned();
+        }
+
+        let mut params: Vec<String> = Vec::new();
+        if let Some(v) = self.presh_time {
+            params.push(format!("PHT {} мс", v));
+        }
+        if let Some(v) = self.vena_contracta {
+            params.push(format!("VC {} см", v));
+        }
+        if let Some(v) = self.ar_jet_width_ratio {
+            params.push(format!("ширина струи {} % от ВТЛЖ", v));
+        }
+        if let Some(v) = self.aortic_flow_reversal {
+            params.push(format!("ретроградный кровоток в нисходящей аорте: {}", v));
+        }
+        push_pisa_params(&mut params, self.ar_eroa, self.ar_regurgitant_volume);
+
+        render_regurgitation("Аортальная", self.aortic_regurgitation_grade, &params)
+    }
+
+    fn render_mitral_regurgitation(&self) -> String {
+        if !self.mitral_regurgitation.is_yes() {
+            return "".to_owned();
+        }
+
+        let mut params: Vec<String> = Vec::new();
+        if let Some(v) = self.mr_vena_contracta {
+            params.push(format!("VC {} см", v));
+        }
+        push_pisa_params(&mut params, self.mr_eroa, self.mr_regurgitant_volume);
+
+        render_regurgitation("Митральная", self.mitral_regurgitation_grade, &params)
+    }
+
+    // предупреждения для проверки перед сохранением протокола
+    pub fn review_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let (Ok(teichholz), Ok(simpson)) =
+            (self.teichholz_ejection_fraction, self.ejection_fraction)
+            && (teichholz - simpson).abs() > EF_DISCREPANCY_LIMIT
+        {
+            warnings.push(format!(
+                "ФВ по Тейхольцу ({} %) отличается от ФВ по Симпсону ({} %) более чем на {} %",
+                PreciseNum::from_float(teichholz, 0),
+                PreciseNum::from_float(simpson, 0),
+                EF_DISCREPANCY_LIMIT
+            ));
+        }
+
+        // не измеренное (Missing) — не ошибка, остальные причины показываются врачу
+        let derived: [(&str, Derived); 19] = [
+            ("ППТ", self.body_surface_area),
+            ("ИО ЛП", self.left_atrium_index),
+            ("ФВ ЛЖ", self.ejection_fraction),
+            ("ИММ ЛЖ", self.left_ventricle_mass_index),
+            ("ОТС", self.relative_wall_thickness),
+            ("ФУ", self.fractional_shortening),
+            ("ФВ по Тейхольцу", self.teichholz_ejection_fraction),
+            ("ИУО", self.stroke_volume_index),
+            ("СИ", self.cardiac_index),
+            ("Qp/Qs", self.qp_qs),
+            ("E/A", self.peak_e_div_peak_a),
+            ("E/e'", self.e_div_e_aps),
+            ("S АК", self.s_doppler),
+            ("безразмерный индекс", self.dimensionless_index),
+            ("EOAi протеза АК", self.aortic_prosthesis_eoa_index),
+            ("EROA АР", self.ar_eroa),
+            ("EROA МР", self.mr_eroa),
+            ("S МК по PHT", self.mitral_s_pht),
+            ("индексированная S АК", self.aortic_valve_area_index),
+        ];
+        for (label, value) in derived {
+            if let Err(reason) = value
+                && reason != NotCalculated::Missing
+            {
+                warnings.push(format!("{} не рассчитано: {}", label, reason));
+            }
+        }
+        warnings
+    }
+
     pub fn render(&self) -> EchoReport {
         EchoReport {
             name: self.name.clone(),
             birthday: self.birthday.format("%d.%m.%Y").to_string(),
             department: self.department.to_string(),
             cardnum: self.cardnum.render_to_string(self.today),
+            rhythm: self.render_rhythm(),
             age: self.age.to_string(),
 
             height: self.height.to_string(),
@@ -344,33 +1009,74 @@ impl CalculatedReportData {
             pulse: self.pulse.to_string(),
 
             aortic_sinus_diameter: self.aortic_sinus_diameter.to_string(),
-            body_surface_area: PreciseNum::from_float(self.body_surface_area, 2).to_string(),
+            body_surface_area: render_derived(self.body_surface_area, 2),
+            bsa_formula: self.bsa_formula.to_string(),
 
             left_ventricle_diastolic_size: self.left_ventricle_diastolic_size.to_string(),
             left_ventricle_systolic_size: self.left_ventricle_systolic_size.to_string(),
             septum_thickness: self.septum_thickness.to_string(),
             posterior_wall_thickness: self.posterior_wall_thickness.to_string(),
 
-            left_ventricle_mass: PreciseNum::from_float(self.left_ventricle_mass, 1).to_string(),
-            left_ventricle_mass_index: PreciseNum::from_float(self.left_ventricle_mass_index, 1)
-                .to_string(),
-            relative_wall_thickness: PreciseNum::from_float(self.relative_wall_thickness, 2)
-                .to_string(),
+            left_ventricle_mass: render_derived(self.left_ventricle_mass, 1),
+            left_ventricle_mass_index: render_derived(self.left_ventricle_mass_index, 1),
+            m_mode_full: format!(
+                " ФУ {} %, по Тейхольцу: КДО {} мл, КСО {} мл, ФВ {} %.",
+                render_derived(self.fractional_shortening, 0),
+                render_derived(self.teichholz_end_diastolic_volume, 0),
+                render_derived(self.teichholz_end_systolic_volume, 0),
+                render_derived(self.teichholz_ejection_fraction, 0)
+            ),
+            relative_wall_thickness: render_derived(self.relative_wall_thickness, 2),
 
             stroke_volume: self.stroke_volume.to_string(),
-            cardiac_index: PreciseNum::from_float(self.cardiac_index, 2).to_string(),
-            cardiac_output: PreciseNum::from_float(self.cardiac_output, 2).to_string(),
+            lvot_diameter_full: match (self.lvot_diameter, self.lvot_vti) {
+                (Some(d), Some(vti)) => format!(", D ВТЛЖ {} см, VTI ВТЛЖ {} см", d, vti),
+                _ => "".to_owned(),
+            },
+            stroke_volume_index_full: match self.stroke_volume_index {
+                Ok(svi) => format!(
+                    "; ИУО {} мл/м² (N≥35 мл/м²){}",
+                    PreciseNum::from_float(svi, 0),
+                    if svi < 35.0 {
+                        " — низкий поток"
+                    } else {
+                        ""
+                    }
+                ),
+                Err(_) => "; ИУО не рассчитан".to_owned(),
+            },
+            cardiac_index: render_derived(self.cardiac_index, 2),
+            cardiac_output: render_derived(self.cardiac_output, 2),
 
             simpson_end_diastolic_volume: self.simpson_end_diastolic_volume.to_string(),
             simpson_end_systolic_volume: self.simpson_end_systolic_volume.to_string(),
-            ejection_fraction: PreciseNum::from_float(self.ejection_fraction, 0).to_string(),
+            ejection_fraction: render_derived(self.ejection_fraction, 0),
 
             ascending_aorta_diameter: self.ascending_aorta_diameter.to_string(),
+            aortic_sinus_norm: self.aortic_size.map_or("".to_owned(), |a| {
+                render_aortic_norm(
+                    a.sinus_expected,
+                    a.sinus_z,
+                    a.sinus_index,
+                    a.sinus_height_index,
+                )
+            }),
+            ascending_aorta_norm: self.aortic_size.map_or("".to_owned(), |a| {
+                render_aortic_norm(
+                    a.ascending_expected,
+                    a.ascending_z,
+                    a.ascending_index,
+                    a.ascending_height_index,
+                )
+            }),
+            aortic_dilation_full: self
+                .aortic_size
+                .map_or("".to_owned(), |a| a.render_to_string()),
 
             left_atrium: self.left_atrium.to_string(),
             left_atrium4: self.left_atrium4.to_string(),
             left_atrium_volume: self.left_atrium_volume.to_string(),
-            left_atrium_index: PreciseNum::from_float(self.left_atrium_index, 1).to_string(),
+            left_atrium_index: render_derived(self.left_atrium_index, 1),
 
             right_atrium_s: self.right_atrium_s.to_string(),
             right_atrium4: self.right_atrium4.to_string(),
@@ -378,6 +1084,11 @@ impl CalculatedReportData {
 
             right_ventricle: self.right_ventricle.to_string(),
             right_ventricle_baz: self.right_ventricle_baz.to_string(),
+            left_ventricle_baz_full: render_to_string(
+                self.left_ventricle_baz,
+                ", ЛЖ базальный ",
+                " см",
+            ),
             right_ventricle_medium_full: render_to_string(
                 self.right_ventricle_medium,
                 ", средний ",
@@ -389,13 +1100,31 @@ impl CalculatedReportData {
                 " см (N<0,5 см). ",
             ),
             tapse_full: render_to_string(self.tapse, "TAPSE: ", " см (N>=1,7 см)"),
+            right_ventricle_function_full: match self.right_ventricle_function.render_to_string() {
+                s if s.is_empty() => s,
+                s if self.tapse.is_some() => format!(", {}", s),
+                s => s,
+            },
+            shunt_full: self.render_shunt(),
+            strain_full: self.render_strain(),
+            strain_bullseye: self.render_strain_bullseye(),
+            wall_motion_full: self
+                .wall_motion_analysis
+                .as_ref()
+                .map_or("".to_owned(), |a| a.render_to_string()),
+            wall_motion_bullseye: self
+                .wall_motion
+                .map_or("".to_owned(), |s| render_wall_motion_bullseye(&s)),
             septum_thickness_baz_full: render_to_string(
                 self.septum_thickness_baz,
                 "Базальный отдел межжелудочковой перегородки (МЖП): ",
                 " см.",
             ),
 
-            shutters_aortal: self.shutters_aortal.render_to_string(),
+            aortic_valve_morphology: self.render_aortic_valve_morphology(),
+            shutters_aortal: self.shutters_aortal.map_or("".to_owned(), |s| {
+                format!("створки {}", s.render_to_string())
+            }),
             opening_amplitude: self.opening_amplitude.to_string(),
             max_velocity: self.max_velocity.to_string(),
             max_grad: self.max_grad.to_string(),
@@ -405,15 +1134,37 @@ impl CalculatedReportData {
                 ". Gr ср ",
                 " мм рт.ст. (N<20 мм рт.ст.). ",
             ),
-            s_doppler_full: render_to_string(
+            s_doppler_full: render_derived_full(
                 self.s_doppler,
+                2,
                 "S отверстия АК ",
-                " см² (по допплеру)",
+                " см² (по уравнению непрерывности)",
+            ),
+            s_planim_full: render_to_string(
+                self.s_planim,
+                if self.s_doppler.is_ok() {
+                    " и "
+                } else {
+                    "S отверстия АК "
+                },
+                " см² (планиметрически)",
             ),
-            s_planim_full: render_to_string(self.s_planim, " и ", " см² (планиметрически)"),
+            aortic_valve_area_index_full: render_derived_full(
+                self.aortic_valve_area_index,
+                2,
+                ", индексированная S АК ",
+                " см²/м²",
+            ),
+            dimensionless_index_full: render_derived_full(
+                self.dimensionless_index,
+                2,
+                ", безразмерный индекс ",
+                "",
+            ),
+            aortic_stenosis_full: self.aortic_stenosis.render_to_string(),
+            aortic_prosthesis_full: self.render_aortic_prosthesis(),
 
-            presh_time_full: render_to_string(self.presh_time, "PHT АР ", " мс, VC АР "),
-            vena_contracta_full: render_to_string(self.vena_contracta, "", " см."),
+            aortic_regurgitation_full: self.render_aortic_regurgitation(),
 
             max_velocity_vt_full: render_to_string(
                 self.max_velocity_vt,
@@ -422,17 +1173,27 @@ impl CalculatedReportData {
             ),
             max_grad_vt_full: render_to_string(self.max_grad_vt, "Gr мах - ", " мм рт.ст."),
 
-            shutters_mitral: self.shutters_mitral.render_to_string(),
-
-            peak_e: self.peak_e.to_string(),
-            peak_a: self.peak_a.to_string(),
-            peak_e_div_peak_a: PreciseNum::from_float(self.peak_e_div_peak_a, 1).to_string(),
-
-            tdi_vel: self.tdi_vel.to_string(),
-
-            e_sept: self.e_sept.to_string(),
-            e_lat: self.e_lat.to_string(),
-            e_div_e_aps: PreciseNum::from_float(self.e_div_e_aps, 1).to_string(),
+            mitral_valve_morphology: render_av_morphology(self.mitral_prosthesis),
+            shutters_mitral: render_av_leaflets(self.shutters_mitral),
+            mitral_antiphase: match self.mitral_prosthesis {
+                Some(_) => "".to_owned(),
+                None => "Противофаза есть. ".to_owned(),
+            },
+
+            mitral_inflow_full: self.render_mitral_inflow(),
+            tdi_full: self.render_tdi(),
+            extended_diastology_full: self
+                .extended_diastology
+                .map_or("".to_owned(), |d| d.render_to_string()),
+            diastolic_function_full: self
+                .diastolic_function
+                .as_ref()
+                .map_or("".to_owned(), |d| d.render_to_string()),
+            mitral_prosthesis_full: render_av_prosthesis(
+                "МК",
+                self.mitral_prosthesis,
+                self.mitral_prosthesis_function,
+            ),
 
             max_velocity_mitral_valve_full: render_to_string(
                 self.max_velocity_mitral_valve,
@@ -450,22 +1211,57 @@ impl CalculatedReportData {
                 " мм рт.ст. (N<5 мм рт.ст).",
             ),
 
+            mitral_presh_time_full: render_to_string(self.mitral_presh_time, " PHT МК ", " мс."),
+            mitral_s_pht_full: render_derived_full(
+                self.mitral_s_pht,
+                1,
+                ", S МК ",
+                " см² (по PHT)",
+            ),
+            mitral_s_planim_full: render_to_string(
+                self.mitral_s_planim,
+                " S МК ",
+                " см² (планиметрически).",
+            ),
+            mitral_stenosis_full: self.mitral_stenosis.render_to_string(),
+
             calts_back_sash: self.calts_back_sash.render_to_string(MitVal::Calcinate),
             posterior_leaflet_base_calcification: self
                 .posterior_leaflet_base_calcification
                 .render_to_string(MitVal::Calcification),
-
+            mitral_regurgitation_full: self.render_mitral_regurgitation(),
+
+            tricuspid_valve_morphology: render_av_morphology(self.tricuspid_prosthesis),
+            shutters_tricuspid: render_av_leaflets(self.shutters_tricuspid),
+            tricuspid_prosthesis_full: render_av_prosthesis(
+                "ТК",
+                self.tricuspid_prosthesis,
+                self.tricuspid_prosthesis_function,
+            ),
+            tricuspid_regurgitation: self
+                .tricuspid_regurgitation
+                .render_to_string("Трикуспидальная"),
+            tricuspid_annulus_full: render_to_string(
+                self.tricuspid_annulus,
+                "Фиброзное кольцо ТК ",
+                " см (N<4,0 см). ",
+            ),
+            tr_vena_contracta_full: render_to_string(self.tr_vena_contracta, "VC ТР ", " см. "),
             max_velocity_tricuspidal_regurgitation: self
                 .max_velocity_tricuspidal_regurgitation
                 .to_string(),
 
+            shutters_pulmonary: self.shutters_pulmonary.render_to_string(),
+            pulmonary_regurgitation: self
+                .pulmonary_regurgitation
+                .render_to_string("Пульмональная"),
             pulmonary_artery: self.pulmonary_artery.to_string(),
-            pulmonary_artery_systolic_pressure: PreciseNum::from_float(
+            pulmonary_artery_systolic_pressure: render_derived(
                 self.pulmonary_artery_systolic_pressure,
                 0,
-            )
-            .to_string(),
+            ),
             max_grad_tricuspidal_regurgitation: self.max_grad_tricuspidal_regurgitation.to_string(),
+            pulmonary_hypertension_full: self.pulmonary_hypertension.render_to_string(),
 
             pulmonary_artery_right_branch_full: render_to_string(
                 self.pulmonary_artery_right_branch,
@@ -491,32 +1287,103 @@ impl CalculatedReportData {
                 "Макс.град. ЛР ",
                 " мм рт.ст.",
             ),
-            pulmonary_artery_med_pressure_full: render_to_string(
-                self.pulmonary_artery_med_pressure,
-                ", Ср.ДЛА ",
-                " мм рт.ст. (до 20 мм рт.ст.).",
+            pulmonary_artery_med_pressure_full: format!(
+                "{}Ср.ДЛА {} мм рт.ст. (до 20 мм рт.ст., {}).",
+                if self.pulmonary_regurgitation_max_grad.is_some() {
+                    ", "
+                } else {
+                    ""
+                },
+                render_derived(self.pulmonary_artery_med_pressure, 0),
+                self.mean_pap_method
+            ),
+
+            pulmonary_regurgitation_end_velocity_full: render_to_string(
+                self.pulmonary_regurgitation_end_velocity,
+                " V кон.-диаст. ЛР ",
+                " м/с.",
             ),
+            pulmonary_stenosis_full: self.pulmonary_stenosis.render_to_string(),
 
             vena: self.vena.to_string(),
+            ivc_collapse: self.ivc_collapse.to_string(),
+            right_atrium_pressure_full: format!(
+                "Давление в ПП {} мм рт.ст. ({}).",
+                self.right_atrium_pressure,
+                if self.right_atrium_pressure_by_ivc {
+                    "оценено по диаметру и коллабированию НПВ"
+                } else {
+                    "задано вручную"
+                }
+            ),
             effusion: self.effusion.render_to_string(),
+            beats_full: self.render_beats(),
 
             today: self.today.format("%d.%m.%Y %H:%M").to_string(),
         }
     }
 }
 
+// диаграмма баллов локальной сократимости
+pub fn render_wall_motion_bullseye(scores: &WallMotionScores) -> String {
+    let fills = scores.map(|m| match m {
+        Some(m) => SegmentFill {
+            color: match m {
+                WallMotion::Normal => [225, 240, 225],
+                WallMotion::Hypokinesis => [250, 220, 90],
+                WallMotion::Akinesis => [240, 130, 60],
+                WallMotion::Dyskinesis => [200, 50, 50],
+                WallMotion::Aneurysm => [120, 40, 120],
+            },
+            label: m.score().to_string(),
+        },
+        None => SegmentFill {
+            color: [200, 200, 200],
+            label: "".to_owned(),
+        },
+    });
+    bullseye::render_base64(&fills)
+}
+
+fn push_pisa_params(params: &mut Vec<String>, eroa: Derived, rvol: Derived) {
+    for (name, value, p, unit) in [("EROA", eroa, 2, " см²"), ("RVol", rvol, 0, " мл")] {
+        let rendered = render_derived_full(value, p, &format!("{} ", name), unit);
+        if !rendered.is_empty() {
+            params.push(rendered);
+        }
+    }
+}
+
+fn render_regurgitation(
+    valve: &str,
+    grade: Option<RegurgitationGrade>,
+    params: &[String],
+) -> String {
+    let grade = match grade {
+        Some(g) => format!(" {}", g),
+        None => "".to_owned(),
+    };
+    if params.is_empty() {
+        format!("{} регургитация{}. ", valve, grade)
+    } else {
+        format!("{} регургитация{} ({}). ", valve, grade, params.join(", "))
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct EchoReport {
     name: String,
     birthday: String,
     department: String,
     cardnum: String,
+    rhythm: String,
     age: String,
     height: String,
     weight: String,
     pulse: String,
     aortic_sinus_diameter: String,
     body_surface_area: String,
+    bsa_formula: String,
     left_ventricle_diastolic_size: String,
     left_ventricle_systolic_size: String,
     septum_thickness: String,
@@ -524,13 +1391,18 @@ pub struct EchoReport {
     left_ventricle_mass: String,
     left_ventricle_mass_index: String,
     relative_wall_thickness: String,
+    m_mode_full: String,
     stroke_volume: String,
+    stroke_volume_index_full: String,
     cardiac_index: String,
     cardiac_output: String,
     simpson_end_diastolic_volume: String,
     simpson_end_systolic_volume: String,
     ejection_fraction: String,
     ascending_aorta_diameter: String,
+    aortic_sinus_norm: String,
+    ascending_aorta_norm: String,
+    aortic_dilation_full: String,
     left_atrium: String,
     left_atrium4: String,
     left_atrium_volume: String,
@@ -540,38 +1412,64 @@ pub struct EchoReport {
     right_atrium_volume: String,
     right_ventricle: String,
     right_ventricle_baz: String,
+    left_ventricle_baz_full: String,
     right_ventricle_medium_full: String,
     right_ventricle_wall_thickness_full: String,
     tapse_full: String,
+    right_ventricle_function_full: String,
     septum_thickness_baz_full: String,
+    shunt_full: String,
+    aortic_valve_morphology: String,
     shutters_aortal: String,
     opening_amplitude: String,
     max_velocity: String,
     max_grad: String,
     mid_grad_full: String,
+    lvot_diameter_full: String,
     s_doppler_full: String,
     s_planim_full: String,
-    presh_time_full: String,
-    vena_contracta_full: String,
+    aortic_valve_area_index_full: String,
+    dimensionless_index_full: String,
+    aortic_stenosis_full: String,
+    aortic_prosthesis_full: String,
+    aortic_regurgitation_full: String,
     max_velocity_vt_full: String,
     max_grad_vt_full: String,
+    mitral_valve_morphology: String,
     shutters_mitral: String,
-    peak_e: String,
-    peak_a: String,
-    peak_e_div_peak_a: String,
-    tdi_vel: String,
-    e_sept: String,
-    e_lat: String,
-    e_div_e_aps: String,
+    mitral_antiphase: String,
+    mitral_inflow_full: String,
+    tdi_full: String,
+    extended_diastology_full: String,
+    strain_full: String,
+    strain_bullseye: String,
+    wall_motion_full: String,
+    wall_motion_bullseye: String,
+    mitral_prosthesis_full: String,
+    diastolic_function_full: String,
     max_velocity_mitral_valve_full: String,
     max_grad_mitral_valve_full: String,
     mid_grad_mitral_valve_full: String,
+    mitral_presh_time_full: String,
+    mitral_s_pht_full: String,
+    mitral_s_planim_full: String,
+    mitral_stenosis_full: String,
     calts_back_sash: String,
     posterior_leaflet_base_calcification: String,
+    mitral_regurgitation_full: String,
+    tricuspid_valve_morphology: String,
+    shutters_tricuspid: String,
+    tricuspid_prosthesis_full: String,
+    tricuspid_regurgitation: String,
+    tricuspid_annulus_full: String,
+    tr_vena_contracta_full: String,
     max_velocity_tricuspidal_regurgitation: String,
+    shutters_pulmonary: String,
+    pulmonary_regurgitation: String,
     pulmonary_artery: String,
     pulmonary_artery_systolic_pressure: String,
     max_grad_tricuspidal_regurgitation: String,
+    pulmonary_hypertension_full: String,
     pulmonary_artery_right_branch_full: String,
     pulmonary_artery_left_branch_full: String,
     max_velocity_in_pulmonary_artery: String,
@@ -579,7 +1477,72 @@ pub struct EchoReport {
     pulmonary_regurgitation_max_velocity_full: String,
     pulmonary_regurgitation_max_grad_full: String,
     pulmonary_artery_med_pressure_full: String,
+    pulmonary_regurgitation_end_velocity_full: String,
+    pulmonary_stenosis_full: String,
     vena: String,
+    ivc_collapse: String,
+    right_atrium_pressure_full: String,
     effusion: String,
+    beats_full: String,
     today: String,
 }
+
+fn render_av_morphology(prosthesis: Option<AvProsthesis>) -> String {
+    match prosthesis {
+        Some(p) => format!("протез ({}, размер {} мм). ", p.kind, p.size),
+        None => "".to_owned(),
+    }
+}
+
+fn render_av_leaflets(leaflets: Option<ValveLeaflets>) -> String {
+    leaflets.map_or("".to_owned(), |s| {
+        format!("створки {}", s.render_to_string())
+    })
+}
+
+fn render_av_prosthesis(
+    valve: &str,
+    prosthesis: Option<AvProsthesis>,
+    function: Option<AvProsthesisFunction>,
+) -> String {
+    let (Some(p), Some(function)) = (prosthesis, function) else {
+        return "".to_owned();
+    };
+    let mut params = vec![
+        format!("E {} м/с", p.peak_e),
+        format!("Gr ср {} мм рт.ст.", p.mid_grad),
+    ];
+    if let Some(pht) = p.presh_time {
+        params.push(format!("PHT {} мс", pht));
+    }
+    if let Some(ratio) = p.vti_ratio {
+        params.push(format!("VTIprMV/VTIВТЛЖ {}", ratio));
+    }
+    let mut out = format!(
+        " Протез {}: {} — {}",
+        valve,
+        params.join(", "),
+        function.grade
+    );
+    if function.regurgitation_suspected {
+        out.push_str(
+            "; высокие скорости при нормальном PHT — вероятна значимая регургитация на протезе",
+        );
+    }
+    match p.paravalvular_leak {
+        ParavalvularLeak::None => {}
+        leak => out.push_str(&format!("; парапротезная фистула {}", leak)),
+    }
+    out.push('.');
+    out
+}
+
+fn render_aortic_norm(expected: f64, z: f64, index: f64, height_index: f64) -> String {
+    format!(
+        "(должный {} см, z = {}; индекс {} см/м², {} см/м роста)",
+        PreciseNum::from_float(expected, 1),
+        PreciseNum::from_float(z, 1),
+        PreciseNum::from_float(index, 2),
+        PreciseNum::from_float(height_index, 2)
+    )
+}
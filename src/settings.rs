@@ -1,3 +1,4 @@
+use crate::report::BsaFormula;
 use dirs_next::document_dir;
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
@@ -11,6 +12,9 @@ use std::{
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Settings {
     save_dir: PathBuf,
+    // если не задана — формула ППТ выбирается при каждом обследовании
+    #[serde(default)]
+    bsa_formula: Option<BsaFormula>,
 }
 
 impl Default for Settings {
@@ -25,7 +29,10 @@ impl Default for Settings {
             }
         };
         fs::create_dir(&sd).unwrap_or_else(|_| {});
-        Self { save_dir: sd }
+        Self {
+            save_dir: sd,
+            bsa_formula: None,
+        }
     }
 }
 
@@ -33,6 +40,10 @@ impl Settings {
     pub fn get_save_dir(&self) -> PathBuf {
         self.save_dir.to_owned()
     }
+
+    pub fn get_bsa_formula(&self) -> Option<BsaFormula> {
+        self.bsa_formula
+    }
 }
 
 pub fn get_exe_dir() -> PathBuf {
@@ -64,7 +75,10 @@ pub fn load_settings() -> Settings {
         }
     }
     let save_dir = choose_save_dir_or_default();
-    let settings = Settings { save_dir };
+    let settings = Settings {
+        save_dir,
+        bsa_formula: None,
+    };
     save_settings(&path, &settings);
     settings
 }
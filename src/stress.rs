@@ -0,0 +1,371 @@
+use crate::bullseye::SEGMENTS;
+use crate::grading::{StressEcho, StressInput, WallMotion, WallMotionAnalysis, WallMotionScores};
+use crate::promptget::{
+    BloodPressure, PreciseNum, RenderToString, ask_selection, calc_age, checked_div,
+    get_blood_pressure, get_date, get_int, get_num, get_num_opt_if, get_string, get_wall_motion,
+    render_derived,
+};
+use crate::report::{CardNumber, Department};
+use crate::reporttypes::render_wall_motion_bullseye;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use std::fmt;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum ProtocolMode {
+    Rest,
+    Stress,
+}
+
+impl ProtocolMode {
+    pub fn text(self) -> &'static str {
+        match self {
+            ProtocolMode::Rest => "ЭхоКГ в покое",
+            ProtocolMode::Stress => "Стресс-ЭхоКГ",
+        }
+    }
+
+    pub fn is_stress(self) -> bool {
+        matches!(self, ProtocolMode::Stress)
+    }
+}
+
+impl fmt::Display for ProtocolMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// целевая ЧСС: 85 % от максимальной возрастной (220 − возраст)
+const TARGET_HEART_RATE_FRACTION: f64 = 0.85;
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum StressType {
+    Dobutamine,
+    Exercise,
+}
+
+impl StressType {
+    pub fn text(self) -> &'static str {
+        match self {
+            StressType::Dobutamine => "добутаминовая",
+            StressType::Exercise => "с физической нагрузкой",
+        }
+    }
+
+    pub fn is_dobutamine(self) -> bool {
+        matches!(self, StressType::Dobutamine)
+    }
+
+    fn load_prompt(self) -> &'static str {
+        match self {
+            StressType::Dobutamine => "Доза добутамина, мкг/кг/мин",
+            StressType::Exercise => "Нагрузка, Вт",
+        }
+    }
+
+    fn load_unit(self) -> &'static str {
+        match self {
+            StressType::Dobutamine => "мкг/кг/мин",
+            StressType::Exercise => "Вт",
+        }
+    }
+}
+
+impl fmt::Display for StressType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum StressStage {
+    Rest,
+    LowDose,
+    Peak,
+    Recovery,
+}
+
+impl StressStage {
+    pub fn text(self) -> &'static str {
+        match self {
+            StressStage::Rest => "Покой",
+            StressStage::LowDose => "Низкая доза",
+            StressStage::Peak => "Пик нагрузки",
+            StressStage::Recovery => "Восстановление",
+        }
+    }
+
+    // доза/нагрузка вводится для низкой дозы и пика
+    fn has_load(self) -> bool {
+        matches!(self, StressStage::LowDose | StressStage::Peak)
+    }
+
+    // низкая доза есть только у добутаминовой пробы
+    fn applies_to(self, stress_type: StressType) -> bool {
+        !matches!(self, StressStage::LowDose) || stress_type.is_dobutamine()
+    }
+}
+
+impl fmt::Display for StressStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumIter)]
+pub enum StopReason {
+    TargetHeartRate,
+    MaxDose,
+    WallMotion,
+    Angina,
+    Arrhythmia,
+    Hypertension,
+    Hypotension,
+    SideEffects,
+    Fatigue,
+}
+
+impl StopReason {
+    pub fn text(self) -> &'static str {
+        match self {
+            StopReason::TargetHeartRate => "достигнута целевая ЧСС",
+            StopReason::MaxDose => "достигнута максимальная доза / нагрузка",
+            StopReason::WallMotion => "появление нарушений локальной сократимости",
+            StopReason::Angina => "ангинозная боль",
+            StopReason::Arrhythmia => "нарушения ритма",
+            StopReason::Hypertension => "гипертензивная реакция (АД > 220/120 мм рт.ст.)",
+            StopReason::Hypotension => "снижение АД",
+            StopReason::SideEffects => "побочные эффекты",
+            StopReason::Fatigue => "утомление",
+        }
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+// этап пробы: ЧСС, АД, доза/нагрузка и баллы 17 сегментов
+#[derive(Debug, Clone, Copy)]
+pub struct StageData {
+    pub stage: StressStage,
+    pub heart_rate: PreciseNum, // p=0
+    pub blood_pressure: BloodPressure,
+    pub load: Option<PreciseNum>, // p=0, optional
+    pub wall_motion: WallMotionScores,
+}
+
+impl StageData {
+    pub fn gather(stage: StressStage, stress_type: StressType) -> Self {
+        println!("{}", stage);
+        let heart_rate = get_num("ЧСС", 0);
+        let blood_pressure = get_blood_pressure("АД");
+        let load = get_num_opt_if(stage.has_load(), stress_type.load_prompt(), 0);
+        let wall_motion =
+            get_wall_motion("Баллы сократимости").map(|s| s.and_then(WallMotion::from_score));
+        Self {
+            stage,
+            heart_rate,
+            blood_pressure,
+            load,
+            wall_motion,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StressRawData {
+    pub name: String,
+    pub birthday: NaiveDate,
+    pub department: Department,
+    pub card_number: CardNumber,
+    pub stress_type: StressType,
+    // по одному на каждый проведенный StressStage, в порядке проведения
+    pub stages: Vec<StageData>,
+    pub stop_reason: StopReason,
+}
+
+impl StressRawData {
+    pub fn gather() -> Self {
+        let name = get_string("ФИО");
+        let birthday = get_date("Дата рождения");
+        let department: Department = ask_selection("Отделение");
+        let card_number: CardNumber = match department {
+            Department::Kdo => CardNumber::Ak(get_int("АК№")),
+            _ => CardNumber::Ib(get_int("ИБ№")),
+        };
+        let stress_type: StressType = ask_selection("Вид пробы");
+        let stages: Vec<StageData> = StressStage::iter()
+            .filter(|stage| stage.applies_to(stress_type))
+            .map(|stage| StageData::gather(stage, stress_type))
+            .collect();
+        let stop_reason: StopReason = ask_selection("Причина прекращения пробы");
+        Self {
+            name,
+            birthday,
+            department,
+            card_number,
+            stress_type,
+            stages,
+            stop_reason,
+        }
+    }
+
+    fn stage(&self, stage: StressStage) -> Option<&StageData> {
+        self.stages.iter().find(|s| s.stage == stage)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StressCalculatedData {
+    pub name: String,
+    pub birthday: NaiveDate,
+    pub department: Department,
+    pub cardnum: CardNumber,
+    pub stress_type: StressType,
+    pub stages: Vec<StageData>,
+    pub stop_reason: StopReason,
+
+    // --- рассчитываемые значения ---
+    pub age: i32,
+    pub target_heart_rate: f64,
+    pub max_heart_rate: f64,
+    pub stage_analyses: Vec<WallMotionAnalysis>,
+    pub stress_echo: StressEcho,
+
+    pub today: DateTime<Local>,
+}
+
+impl StressCalculatedData {
+    pub fn from_raw(raw: &StressRawData, today: DateTime<Local>) -> Self {
+        let age = calc_age(raw.birthday, today);
+        let target_heart_rate = TARGET_HEART_RATE_FRACTION * (220 - age) as f64;
+        let max_heart_rate = raw
+            .stages
+            .iter()
+            .map(|s| s.heart_rate.value())
+            .fold(0.0, f64::max);
+        let stage_analyses = raw
+            .stages
+            .iter()
+            .map(|s| WallMotionAnalysis::assess(&s.wall_motion))
+            .collect();
+
+        let scores = |stage| raw.stage(stage).map_or([None; SEGMENTS], |s| s.wall_motion);
+        let stress_echo = StressEcho::assess(&StressInput {
+            rest: &scores(StressStage::Rest),
+            low_dose: raw.stage(StressStage::LowDose).map(|s| &s.wall_motion),
+            peak: &scores(StressStage::Peak),
+            recovery: &scores(StressStage::Recovery),
+            target_reached: max_heart_rate >= target_heart_rate,
+        });
+
+        Self {
+            name: raw.name.clone(),
+            birthday: raw.birthday,
+            department: raw.department,
+            cardnum: raw.card_number,
+            stress_type: raw.stress_type,
+            stages: raw.stages.clone(),
+            stop_reason: raw.stop_reason,
+            age,
+            target_heart_rate,
+            max_heart_rate,
+            stage_analyses,
+            stress_echo,
+            today,
+        }
+    }
+
+    fn render_stage(&self, stage: StressStage) -> (String, String) {
+        let Some((data, analysis)) = self
+            .stages
+            .iter()
+            .zip(&self.stage_analyses)
+            .find(|(s, _)| s.stage == stage)
+        else {
+            return ("".to_owned(), "".to_owned());
+        };
+        let load = match data.load {
+            Some(v) => format!(", {} {}", v, self.stress_type.load_unit()),
+            None => "".to_owned(),
+        };
+        (
+            format!(
+                "{}{}: ЧСС {} уд/мин, АД {} мм рт.ст. {}",
+                stage,
+                load,
+                data.heart_rate,
+                data.blood_pressure,
+                analysis.render_to_string()
+            ),
+            render_wall_motion_bullseye(&data.wall_motion),
+        )
+    }
+
+    pub fn render(&self) -> StressReport {
+        let (rest_stage_full, rest_bullseye) = self.render_stage(StressStage::Rest);
+        let (low_dose_stage_full, low_dose_bullseye) = self.render_stage(StressStage::LowDose);
+        let (peak_stage_full, peak_bullseye) = self.render_stage(StressStage::Peak);
+        let (recovery_stage_full, recovery_bullseye) = self.render_stage(StressStage::Recovery);
+        StressReport {
+            name: self.name.clone(),
+            birthday: self.birthday.format("%d.%m.%Y").to_string(),
+            department: self.department.to_string(),
+            cardnum: self.cardnum.render_to_string(self.today),
+            age: self.age.to_string(),
+
+            stress_type: self.stress_type.to_string(),
+            target_heart_rate: PreciseNum::from_float(self.target_heart_rate, 0).to_string(),
+            max_heart_rate: PreciseNum::from_float(self.max_heart_rate, 0).to_string(),
+            target_heart_rate_percent: render_derived(
+                checked_div(self.max_heart_rate, self.target_heart_rate, "целевая ЧСС")
+                    .map(|v| v * 100.0),
+                0,
+            ),
+
+            rest_stage_full,
+            rest_bullseye,
+            low_dose_stage_full,
+            low_dose_bullseye,
+            peak_stage_full,
+            peak_bullseye,
+            recovery_stage_full,
+            recovery_bullseye,
+
+            stop_reason: self.stop_reason.to_string(),
+            stress_conclusion: self.stress_echo.render_to_string(),
+
+            today: self.today.format("%d.%m.%Y %H:%M").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StressReport {
+    name: String,
+    birthday: String,
+    department: String,
+    cardnum: String,
+    age: String,
+    stress_type: String,
+    target_heart_rate: String,
+    max_heart_rate: String,
+    target_heart_rate_percent: String,
+    rest_stage_full: String,
+    rest_bullseye: String,
+    low_dose_stage_full: String,
+    low_dose_bullseye: String,
+    peak_stage_full: String,
+    peak_bullseye: String,
+    recovery_stage_full: String,
+    recovery_bullseye: String,
+    stop_reason: String,
+    stress_conclusion: String,
+    today: String,
+}